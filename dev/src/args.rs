@@ -0,0 +1,417 @@
+use crate::batch;
+use crate::batch::{Plan, Sweep};
+use crate::config;
+use crate::config::Config;
+use crate::frames::Format;
+use crate::params::Params;
+use crate::plot::Paper;
+use crate::print;
+use crate::print::Print;
+use crate::replay;
+use crate::replay::{Log, Session};
+use crate::snapshot;
+use crate::snapshot::Snapshot;
+use rand::Rng;
+use serde::de::DeserializeOwned;
+use std::env;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::process;
+
+const USAGE: &str = "usage: [--frames <dir>] [--svg <dir>] [--plot <dir>] \
+                     [--paper <a4|a3|WxH>] [--margin <mm>] \
+                     [--seed <u64>] [--steps <u64>] \
+                     [--config <file.{toml,json}>] \
+                     [--restore <snapshot.json>] \
+                     [--record <log.json> | --replay <log.json>] \
+                     [--headless --steps <u64> --out <file.{png,svg}> \
+                     [--print-size <px> | --print-cm <cm>] [--dpi <dpi>] \
+                     [--supersample <n>]] \
+                     [--batch <dir> --steps <u64> [--seeds <u64>..<u64>] \
+                     [--sweep <parameter>=<from>..<to>:<count>]] \
+                     [--<parameter> <value>]...";
+
+const DPI: f64 = 300.0;
+const CM_PER_INCH: f64 = 2.54;
+
+const SUPERSAMPLE_MAX: u32 = 8;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    pub frames: Option<PathBuf>,
+    pub svg: Option<PathBuf>,
+    pub plot: Option<PathBuf>,
+    pub paper: Paper,
+    pub seed: Option<u64>,
+    pub steps: Option<u64>,
+    pub config: Option<PathBuf>,
+    pub restore: Option<PathBuf>,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub headless: bool,
+    pub out: Option<PathBuf>,
+    pub print_size: Option<u32>,
+    pub print_cm: Option<f64>,
+    pub dpi: Option<f64>,
+    pub supersample: Option<u32>,
+    pub batch: Option<PathBuf>,
+    pub seeds: Option<Range<u64>>,
+    pub sweep: Option<Sweep>,
+    pub params: Vec<(String, String)>,
+}
+
+impl Args {
+    pub fn parse<I>(args: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut parsed: Self = Self::default();
+        let mut paper: Option<String> = None;
+        let mut margin: Option<f32> = None;
+        let mut args: I::IntoIter = args.into_iter();
+        macro_rules! value {
+            ($flag:expr $(,)?) => {
+                args.next()
+                    .ok_or_else(|| format!("{} needs a value", $flag))?
+            };
+        }
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--frames" => parsed.frames = Some(value!(arg).into()),
+                "--svg" => parsed.svg = Some(value!(arg).into()),
+                "--plot" => parsed.plot = Some(value!(arg).into()),
+                "--paper" => paper = Some(value!(arg)),
+                "--margin" => {
+                    let value: String = value!(arg);
+                    margin = match value.parse::<f32>() {
+                        Ok(margin) if 0.0 <= margin => Some(margin),
+                        _ => return Err(format!("bad margin `{}`", value)),
+                    }
+                }
+                "--seed" => {
+                    let value: String = value!(arg);
+                    match value.parse::<u64>() {
+                        Ok(seed) => parsed.seed = Some(seed),
+                        _ => return Err(format!("bad seed `{}`", value)),
+                    }
+                }
+                "--steps" => {
+                    let value: String = value!(arg);
+                    match value.parse::<u64>() {
+                        Ok(steps) => parsed.steps = Some(steps),
+                        _ => return Err(format!("bad steps `{}`", value)),
+                    }
+                }
+                "--config" => parsed.config = Some(value!(arg).into()),
+                "--restore" => parsed.restore = Some(value!(arg).into()),
+                "--record" => parsed.record = Some(value!(arg).into()),
+                "--replay" => parsed.replay = Some(value!(arg).into()),
+                "--headless" => parsed.headless = true,
+                "--out" => parsed.out = Some(value!(arg).into()),
+                "--print-size" => {
+                    let value: String = value!(arg);
+                    match value.parse::<u32>() {
+                        Ok(size)
+                            if (0 < size) && (size <= print::EDGE_MAX) =>
+                        {
+                            parsed.print_size = Some(size)
+                        }
+                        _ => {
+                            return Err(format!("bad print size `{}`", value))
+                        }
+                    }
+                }
+                "--print-cm" => {
+                    let value: String = value!(arg);
+                    match value.parse::<f64>() {
+                        Ok(cm) if (0.0 < cm) && cm.is_finite() => {
+                            parsed.print_cm = Some(cm)
+                        }
+                        _ => return Err(format!("bad print cm `{}`", value)),
+                    }
+                }
+                "--dpi" => {
+                    let value: String = value!(arg);
+                    match value.parse::<f64>() {
+                        Ok(dpi) if (0.0 < dpi) && dpi.is_finite() => {
+                            parsed.dpi = Some(dpi)
+                        }
+                        _ => return Err(format!("bad dpi `{}`", value)),
+                    }
+                }
+                "--supersample" => {
+                    let value: String = value!(arg);
+                    match value.parse::<u32>() {
+                        Ok(n) if (0 < n) && (n <= SUPERSAMPLE_MAX) => {
+                            parsed.supersample = Some(n)
+                        }
+                        _ => {
+                            return Err(format!("bad supersample `{}`", value))
+                        }
+                    }
+                }
+                "--batch" => parsed.batch = Some(value!(arg).into()),
+                "--seeds" => {
+                    let value: String = value!(arg);
+                    let seeds: Option<Range<u64>> =
+                        value.split_once("..").and_then(|(from, to)| {
+                            Some(from.parse().ok()?..to.parse().ok()?)
+                        });
+                    match seeds {
+                        Some(seeds) if !seeds.is_empty() => {
+                            parsed.seeds = Some(seeds)
+                        }
+                        _ => return Err(format!("bad seeds `{}`", value)),
+                    }
+                }
+                "--sweep" => parsed.sweep = Some(Sweep::parse(&value!(arg))?),
+                flag if flag.starts_with("--") => {
+                    let name: String = flag[2..].replace('-', "_");
+                    parsed.params.push((name, value!(arg)));
+                }
+                _ => return Err(format!("unknown argument `{}`", arg)),
+            }
+        }
+        match (&parsed.restore, &parsed.record, &parsed.replay) {
+            (_, Some(_), Some(_)) => {
+                return Err("--record and --replay conflict".to_string())
+            }
+            (Some(_), Some(_), _) | (Some(_), _, Some(_)) => {
+                return Err(
+                    "--restore cannot be recorded or replayed".to_string()
+                )
+            }
+            _ => (),
+        }
+        match &parsed.out {
+            Some(out) if parsed.headless => {
+                Format::of(out)?;
+            }
+            Some(_) => return Err("--out needs --headless".to_string()),
+            None if parsed.headless => {
+                return Err("--headless needs --out".to_string())
+            }
+            None => (),
+        }
+        if (parsed.print_size.is_some()
+            || parsed.print_cm.is_some()
+            || parsed.dpi.is_some()
+            || parsed.supersample.is_some())
+            && !parsed.headless
+        {
+            return Err("--print-size, --print-cm, --dpi and --supersample \
+                        need --headless"
+                .to_string());
+        }
+        if parsed.print_size.is_some() && parsed.print_cm.is_some() {
+            return Err("--print-size and --print-cm conflict".to_string());
+        }
+        if (parsed.seeds.is_some() || parsed.sweep.is_some())
+            && parsed.batch.is_none()
+        {
+            return Err("--seeds and --sweep need --batch".to_string());
+        }
+        if parsed.batch.is_some()
+            && (parsed.headless
+                || parsed.frames.is_some()
+                || parsed.restore.is_some()
+                || parsed.record.is_some()
+                || parsed.replay.is_some())
+        {
+            return Err("--batch cannot take --headless, --frames, \
+                        --restore, --record or --replay"
+                .to_string());
+        }
+        if parsed.headless
+            && (parsed.frames.is_some()
+                || parsed.record.is_some()
+                || parsed.replay.is_some())
+        {
+            return Err(
+                "--headless cannot take --frames, --record or --replay"
+                    .to_string(),
+            );
+        }
+        parsed.paper = Paper::parse(
+            paper.as_deref().unwrap_or("a4"),
+            margin.unwrap_or(Paper::A4.margin),
+        )?;
+        Ok(parsed)
+    }
+
+    /* NOTE: Without `--seed` a seed is still drawn up front, so that it can
+     * be printed and the run reproduced.
+     */
+    pub fn seed(&self) -> u64 {
+        self.seed.unwrap_or_else(|| rand::thread_rng().gen())
+    }
+
+    /* NOTE: A config file only fills in what the command line leaves out,
+     * so flags can tweak a saved preset.
+     */
+    pub fn apply<P: Params>(&mut self, params: &mut P) -> Result<(), String> {
+        if let Some(path) = &self.config {
+            let config: Config<P> = config::load(path)?;
+            *params = config.params;
+            self.seed = self.seed.or(config.seed);
+            self.steps = self.steps.or(config.steps);
+        }
+        if (self.headless || self.batch.is_some()) && self.steps.is_none() {
+            return Err("--headless and --batch need --steps".to_string());
+        }
+        self.apply_flags(params)
+    }
+
+    pub fn apply_flags(&self, params: &mut impl Params) -> Result<(), String> {
+        for (name, value) in &self.params {
+            params.set(name, value)?;
+        }
+        params.validate()
+    }
+
+    /* NOTE: A snapshot brings its own params, with only flags applied on
+     * top, so a run can branch from a checkpoint with a tweak.
+     */
+    pub fn restore<P, S>(&self, params: &mut P) -> Option<Snapshot<P, S>>
+    where
+        P: Params,
+        S: DeserializeOwned,
+    {
+        let path: &PathBuf = self.restore.as_ref()?;
+        match snapshot::load(path).and_then(|snapshot: Snapshot<P, S>| {
+            *params = snapshot.params.clone();
+            self.apply_flags(params)?;
+            Ok(snapshot)
+        }) {
+            Ok(snapshot) => Some(snapshot),
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(2)
+            }
+        }
+    }
+
+    /* NOTE: A replay brings its own seed, params and step count and takes no
+     * flags on top, otherwise it would not reproduce the recording.
+     */
+    pub fn session<P: Params>(&mut self, params: &mut P) -> Session<P> {
+        if let Some(path) = &self.replay {
+            match replay::load(path) {
+                Ok(log) => {
+                    let log: Log<P> = log;
+                    *params = log.params.clone();
+                    self.seed = Some(log.seed);
+                    self.steps = log.steps;
+                    Session::Replay { log, cursor: 0 }
+                }
+                Err(error) => {
+                    eprintln!("{}", error);
+                    process::exit(2)
+                }
+            }
+        } else if let Some(path) = &self.record {
+            let seed: u64 = self.seed();
+            self.seed = Some(seed);
+            Session::Record {
+                path: path.clone(),
+                log: Log::new(seed, params.clone()),
+            }
+        } else {
+            Session::Live
+        }
+    }
+
+    /* NOTE: With neither `--print-size` nor `--print-cm` the output keeps
+     * the sketch's own size; `--print-cm` without `--dpi` assumes 300.
+     */
+    fn make_print(
+        &self,
+        width: f64,
+        height: f64,
+        samples: u8,
+    ) -> Result<Print, String> {
+        let mut print: Print = Print::new(width, height, samples);
+        let dpi: Option<f64> = match self.print_cm {
+            Some(_) => Some(self.dpi.unwrap_or(DPI)),
+            None => self.dpi,
+        };
+        let edge: Option<u32> = match (self.print_size, self.print_cm, dpi) {
+            (Some(size), _, _) => Some(size),
+            (None, Some(cm), Some(dpi)) => {
+                let edge: f64 = ((cm / CM_PER_INCH) * dpi).round();
+                if !((1.0 <= edge) && (edge <= f64::from(print::EDGE_MAX))) {
+                    return Err(format!(
+                        "a {} cm print at {} dpi is {} pixels across",
+                        cm, dpi, edge,
+                    ));
+                }
+                Some(edge as u32)
+            }
+            _ => None,
+        };
+        if let Some(edge) = edge {
+            print = print.with_edge(edge);
+        }
+        let print: Print = Print {
+            supersample: self.supersample.unwrap_or(1),
+            dpi,
+            ..print
+        };
+        print.check()?;
+        Ok(print)
+    }
+
+    pub fn print(&self, width: f64, height: f64, samples: u8) -> Print {
+        match self.make_print(width, height, samples) {
+            Ok(print) => print,
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(2)
+            }
+        }
+    }
+
+    /* NOTE: Without `--seeds` a batch sweeps the one seed `seed` gives.
+     */
+    pub fn batch<P, F>(&self, params: &P, size: F) -> Option<Plan<P>>
+    where
+        P: Params,
+        F: Fn(&P) -> (u32, u32),
+    {
+        let dir: &PathBuf = self.batch.as_ref()?;
+        let seeds: Vec<u64> = match &self.seeds {
+            Some(seeds) => seeds.clone().collect(),
+            None => vec![self.seed()],
+        };
+        match batch::plan(
+            dir.clone(),
+            self.steps.unwrap_or(0),
+            &seeds,
+            self.sweep.as_ref(),
+            params,
+            size,
+        ) {
+            Ok(plan) => Some(plan),
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(2)
+            }
+        }
+    }
+
+    pub fn from_env(params: &mut impl Params) -> Self {
+        let defaults: Vec<(&'static str, String)> = params.fields();
+        match Self::parse(env::args().skip(1))
+            .and_then(|mut args| args.apply(params).map(|_| args))
+        {
+            Ok(args) => args,
+            Err(error) => {
+                eprintln!("{}\n{}\nparameters:", error, USAGE);
+                for (name, value) in defaults {
+                    eprintln!("  --{} <{}>", name.replace('_', "-"), value);
+                }
+                process::exit(2)
+            }
+        }
+    }
+}
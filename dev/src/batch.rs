@@ -0,0 +1,335 @@
+use crate::config;
+use crate::frames;
+use crate::params::Params;
+use crate::raster::Raster;
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+const CHANNELS: usize = 4;
+
+const GAP: usize = 8;
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+const GLYPH_SCALE: usize = 3;
+const LABEL_HEIGHT: usize = (GLYPH_HEIGHT * GLYPH_SCALE) + GAP;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sweep {
+    pub name: String,
+    pub from: f64,
+    pub to: f64,
+    pub count: usize,
+}
+
+impl Sweep {
+    pub fn parse(sweep: &str) -> Result<Self, String> {
+        let error = || format!("bad sweep `{}`", sweep);
+        let (name, range): (&str, &str) =
+            sweep.split_once('=').ok_or_else(error)?;
+        let (range, count): (&str, &str) =
+            range.split_once(':').ok_or_else(error)?;
+        let (from, to): (&str, &str) =
+            range.split_once("..").ok_or_else(error)?;
+        let count: usize = match count.parse::<usize>() {
+            Ok(count) if 0 < count => count,
+            _ => return Err(error()),
+        };
+        Ok(Self {
+            name: name.replace('-', "_"),
+            from: from.parse().map_err(|_| error())?,
+            to: to.parse().map_err(|_| error())?,
+            count,
+        })
+    }
+
+    /* NOTE: Both ends are included. Values go through `f32` on the way to
+     * text, so that `0.1` steps do not print as `0.30000000000000004`.
+     */
+    pub fn values(&self) -> Vec<String> {
+        if self.count == 1 {
+            return vec![(self.from as f32).to_string()];
+        }
+        let step: f64 = (self.to - self.from) / ((self.count - 1) as f64);
+        (0..self.count)
+            .map(|i| ((self.from + (step * (i as f64))) as f32).to_string())
+            .collect()
+    }
+}
+
+#[derive(Serialize)]
+pub struct Tile<P> {
+    pub index: usize,
+    pub label: String,
+    pub file: String,
+    pub seed: u64,
+    pub params: P,
+}
+
+pub struct Plan<P> {
+    pub dir: PathBuf,
+    pub steps: u64,
+    pub columns: usize,
+    pub size: (u32, u32),
+    pub tiles: Vec<Tile<P>>,
+}
+
+/* NOTE: Every tile carries its full params, so any entry can be copied
+ * out as a config to rerun that tile alone.
+ */
+#[derive(Serialize)]
+struct Manifest<'a, P> {
+    sketch: &'a str,
+    sheet: String,
+    steps: u64,
+    columns: usize,
+    tiles: &'a [Tile<P>],
+}
+
+/* NOTE: One row per seed and one column per swept value; without a sweep
+ * the seeds are laid out as close to square as they fit. `size` gives the
+ * raster a tile's params render to, so a sweep that would change it fails
+ * here rather than after every tile before it has rendered.
+ */
+pub fn plan<P, F>(
+    dir: PathBuf,
+    steps: u64,
+    seeds: &[u64],
+    sweep: Option<&Sweep>,
+    params: &P,
+    size: F,
+) -> Result<Plan<P>, String>
+where
+    P: Params,
+    F: Fn(&P) -> (u32, u32),
+{
+    let values: Vec<Option<String>> = match sweep {
+        Some(sweep) => sweep.values().into_iter().map(Some).collect(),
+        None => vec![None],
+    };
+    let mut tiles: Vec<Tile<P>> =
+        Vec::with_capacity(seeds.len() * values.len());
+    let mut first: Option<(u32, u32)> = None;
+    for seed in seeds {
+        for value in &values {
+            let index: usize = tiles.len();
+            let mut params: P = params.clone();
+            let mut label: String = format!("#{} seed={}", index, seed);
+            if let (Some(sweep), Some(value)) = (sweep, value) {
+                params.set(&sweep.name, value)?;
+                label.push_str(&format!(" {}={}", sweep.name, value));
+            }
+            params.validate()?;
+            let (width, height): (u32, u32) = size(&params);
+            if *first.get_or_insert((width, height)) != (width, height) {
+                return Err(format!(
+                    "tile {} is {}x{}, but every tile in a batch has to be \
+                     the same size",
+                    label, width, height,
+                ));
+            }
+            tiles.push(Tile {
+                index,
+                label,
+                file: format!("{:03}.png", index),
+                seed: *seed,
+                params,
+            });
+        }
+    }
+    let columns: usize = match sweep {
+        Some(sweep) => sweep.count,
+        None => (tiles.len() as f64).sqrt().ceil() as usize,
+    };
+    Ok(Plan {
+        dir,
+        steps,
+        columns,
+        size: first.unwrap_or((0, 0)),
+        tiles,
+    })
+}
+
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_ascii_lowercase() {
+        '0' => [7, 5, 5, 5, 7],
+        '1' => [2, 6, 2, 2, 7],
+        '2' => [7, 1, 7, 4, 7],
+        '3' => [7, 1, 7, 1, 7],
+        '4' => [5, 5, 7, 1, 1],
+        '5' => [7, 4, 7, 1, 7],
+        '6' => [7, 4, 7, 5, 7],
+        '7' => [7, 1, 1, 1, 1],
+        '8' => [7, 5, 7, 5, 7],
+        '9' => [7, 5, 7, 1, 7],
+        'a' => [2, 5, 7, 5, 5],
+        'b' => [6, 5, 6, 5, 6],
+        'c' => [3, 4, 4, 4, 3],
+        'd' => [6, 5, 5, 5, 6],
+        'e' => [7, 4, 6, 4, 7],
+        'f' => [7, 4, 6, 4, 4],
+        'g' => [3, 4, 5, 5, 3],
+        'h' => [5, 5, 7, 5, 5],
+        'i' => [7, 2, 2, 2, 7],
+        'j' => [1, 1, 1, 5, 2],
+        'k' => [5, 5, 6, 5, 5],
+        'l' => [4, 4, 4, 4, 7],
+        'm' => [5, 7, 7, 5, 5],
+        'n' => [6, 5, 5, 5, 5],
+        'o' => [2, 5, 5, 5, 2],
+        'p' => [6, 5, 6, 4, 4],
+        'q' => [2, 5, 5, 6, 3],
+        'r' => [6, 5, 6, 5, 5],
+        's' => [3, 4, 2, 1, 6],
+        't' => [7, 2, 2, 2, 2],
+        'u' => [5, 5, 5, 5, 7],
+        'v' => [5, 5, 5, 5, 2],
+        'w' => [5, 5, 7, 7, 5],
+        'x' => [5, 5, 2, 5, 5],
+        'y' => [5, 5, 2, 2, 2],
+        'z' => [7, 1, 2, 4, 7],
+        '.' => [0, 0, 0, 0, 2],
+        '-' => [0, 0, 7, 0, 0],
+        '_' => [0, 0, 0, 0, 7],
+        '=' => [0, 7, 0, 7, 0],
+        '#' => [5, 7, 5, 7, 5],
+        _ => [0; GLYPH_HEIGHT],
+    }
+}
+
+fn to_rgba(color: [f32; 4]) -> [u8; CHANNELS] {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    [channel(color[0]), channel(color[1]), channel(color[2]), 255]
+}
+
+struct Sheet {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Sheet {
+    fn new(width: usize, height: usize, background: [u8; CHANNELS]) -> Self {
+        Self {
+            width,
+            height,
+            pixels: background.repeat(width * height),
+        }
+    }
+
+    fn blit(&mut self, x: usize, y: usize, raster: &Raster) {
+        let width: usize = raster.width() as usize;
+        for (row, line) in raster.pixels().chunks(width * CHANNELS).enumerate()
+        {
+            let start: usize = (((y + row) * self.width) + x) * CHANNELS;
+            self.pixels[start..(start + line.len())].copy_from_slice(line);
+        }
+    }
+
+    fn text(
+        &mut self,
+        x: usize,
+        y: usize,
+        limit: usize,
+        text: &str,
+        ink: [u8; CHANNELS],
+    ) {
+        let advance: usize = (GLYPH_WIDTH + 1) * GLYPH_SCALE;
+        for (i, c) in text.chars().enumerate() {
+            let left: usize = x + (i * advance);
+            if (x + limit) < (left + advance) {
+                return;
+            }
+            for (row, bits) in glyph(c).iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    if ((bits >> (GLYPH_WIDTH - 1 - column)) & 1) == 0 {
+                        continue;
+                    }
+                    for dy in 0..GLYPH_SCALE {
+                        for dx in 0..GLYPH_SCALE {
+                            let px: usize = left + (column * GLYPH_SCALE) + dx;
+                            let py: usize = y + (row * GLYPH_SCALE) + dy;
+                            let start: usize =
+                                ((py * self.width) + px) * CHANNELS;
+                            self.pixels[start..(start + CHANNELS)]
+                                .copy_from_slice(&ink);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/* NOTE: Tiles are placed as they finish rather than held until the end,
+ * so a long sweep only ever keeps one full-size raster alive.
+ */
+pub fn run<P, F>(
+    plan: &Plan<P>,
+    name: &str,
+    colors: [[f32; 4]; 2],
+    mut render: F,
+) -> io::Result<PathBuf>
+where
+    P: Params,
+    F: FnMut(&Tile<P>) -> Raster,
+{
+    fs::create_dir_all(&plan.dir)?;
+    let [background, ink]: [[u8; CHANNELS]; 2] =
+        [to_rgba(colors[0]), to_rgba(colors[1])];
+    let rows: usize = plan.tiles.len().div_ceil(plan.columns);
+    let (width, height): (usize, usize) =
+        (plan.size.0 as usize, plan.size.1 as usize);
+    let mut sheet: Option<Sheet> = None;
+    for tile in &plan.tiles {
+        let raster: Raster = render(tile);
+        if (raster.width(), raster.height()) != plan.size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "tile {} rendered at {}x{} instead of the planned {}x{}",
+                    tile.label,
+                    raster.width(),
+                    raster.height(),
+                    plan.size.0,
+                    plan.size.1,
+                ),
+            ));
+        }
+        let path: PathBuf = plan.dir.join(&tile.file);
+        frames::write_raster(&path, &raster)?;
+        println!("{}", path.display());
+        let sheet: &mut Sheet = sheet.get_or_insert_with(|| {
+            Sheet::new(
+                (plan.columns * (width + GAP)) + GAP,
+                (rows * (height + LABEL_HEIGHT + GAP)) + GAP,
+                background,
+            )
+        });
+        let x: usize = GAP + ((tile.index % plan.columns) * (width + GAP));
+        let y: usize = GAP
+            + ((tile.index / plan.columns) * (height + LABEL_HEIGHT + GAP));
+        sheet.blit(x, y, &raster);
+        sheet.text(x, y + height + (GAP / 2), width, &tile.label, ink);
+    }
+    let sheet_file: String = format!("{}-sheet.png", name);
+    let path: PathBuf = plan.dir.join(&sheet_file);
+    if let Some(sheet) = sheet {
+        frames::write_png(
+            &path,
+            sheet.width as u32,
+            sheet.height as u32,
+            &sheet.pixels,
+        )?;
+    }
+    let manifest: Manifest<P> = Manifest {
+        sketch: name,
+        sheet: sheet_file,
+        steps: plan.steps,
+        columns: plan.columns,
+        tiles: &plan.tiles,
+    };
+    config::write(&plan.dir.join("manifest.toml"), &manifest)?;
+    Ok(path)
+}
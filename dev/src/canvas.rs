@@ -0,0 +1,112 @@
+use graphics::math::Matrix2d;
+use graphics::{Context, Graphics, Line, Transformed};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Style {
+    pub color: [f32; 4],
+    pub width: f64,
+}
+
+impl Style {
+    pub const fn fill(color: [f32; 4]) -> Self {
+        Self { color, width: 0.0 }
+    }
+
+    pub const fn stroke(color: [f32; 4], width: f64) -> Self {
+        Self { color, width }
+    }
+}
+
+/* NOTE: Coordinates have their origin in the middle of the canvas. Fills
+ * ignore `Style::width`; strokes treat it as the full width of the line.
+ */
+pub trait Canvas {
+    fn clear(&mut self, color: [f32; 4]);
+
+    fn line(&mut self, style: &Style, line: [f64; 4]);
+
+    fn polyline(&mut self, style: &Style, points: &[[f64; 2]]);
+
+    fn circle(&mut self, style: &Style, center: [f64; 2], radius: f64);
+
+    fn rectangle(&mut self, style: &Style, rect: [f64; 4]);
+}
+
+pub struct GraphicsCanvas<'a, G: Graphics> {
+    context: Context,
+    transform: Matrix2d,
+    graphics: &'a mut G,
+}
+
+impl<'a, G: Graphics> GraphicsCanvas<'a, G> {
+    pub fn new(context: Context, graphics: &'a mut G) -> Self {
+        let [width, height]: [f64; 2] = context.get_view_size();
+        Self::view(context, graphics, [width / 2.0, height / 2.0], 1.0)
+    }
+
+    /* NOTE: Puts the sketch origin at `center`, in pixels, and scales the
+     * sketch around it, so strokes and radii grow with everything else.
+     */
+    pub fn view(
+        context: Context,
+        graphics: &'a mut G,
+        center: [f64; 2],
+        scale: f64,
+    ) -> Self {
+        Self {
+            context,
+            transform: context
+                .transform
+                .trans(center[0], center[1])
+                .scale(scale, scale),
+            graphics,
+        }
+    }
+}
+
+impl<'a, G: Graphics> Canvas for GraphicsCanvas<'a, G> {
+    fn clear(&mut self, color: [f32; 4]) {
+        graphics::clear(color, self.graphics);
+    }
+
+    fn line(&mut self, style: &Style, line: [f64; 4]) {
+        graphics::line(
+            style.color,
+            style.width / 2.0,
+            line,
+            self.transform,
+            self.graphics,
+        );
+    }
+
+    fn polyline(&mut self, style: &Style, points: &[[f64; 2]]) {
+        /* NOTE: Round ends fill in the joints between segments. */
+        let line: Line = Line::new_round(style.color, style.width / 2.0);
+        for pair in points.windows(2) {
+            line.draw(
+                [pair[0][0], pair[0][1], pair[1][0], pair[1][1]],
+                &self.context.draw_state,
+                self.transform,
+                self.graphics,
+            );
+        }
+    }
+
+    fn circle(&mut self, style: &Style, center: [f64; 2], radius: f64) {
+        graphics::ellipse(
+            style.color,
+            [
+                center[0] - radius,
+                center[1] - radius,
+                radius * 2.0,
+                radius * 2.0,
+            ],
+            self.transform,
+            self.graphics,
+        );
+    }
+
+    fn rectangle(&mut self, style: &Style, rect: [f64; 4]) {
+        graphics::rectangle(style.color, rect, self.transform, self.graphics);
+    }
+}
@@ -0,0 +1,106 @@
+use crate::params::Params;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(bound(deserialize = "P: Params"), default, deny_unknown_fields)]
+pub struct Config<P> {
+    pub seed: Option<u64>,
+    pub steps: Option<u64>,
+    pub params: P,
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension() == Some(OsStr::new("json"))
+}
+
+/* NOTE: Files ending in `.json` are read and written as JSON, anything
+ * else as TOML.
+ */
+fn decode<T: DeserializeOwned>(path: &Path, text: &str) -> Result<T, String> {
+    let value: Result<T, String> = if is_json(path) {
+        serde_json::from_str(text).map_err(|error| error.to_string())
+    } else {
+        toml::from_str(text).map_err(|error| error.to_string())
+    };
+    value.map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+fn encode<T: Serialize>(path: &Path, value: &T) -> Result<String, String> {
+    if is_json(path) {
+        serde_json::to_string_pretty(value)
+            .map(|text| text + "\n")
+            .map_err(|error| error.to_string())
+    } else {
+        toml::to_string(value).map_err(|error| error.to_string())
+    }
+}
+
+pub fn read<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let text: String = fs::read_to_string(path)
+        .map_err(|error| format!("{}: {}", path.display(), error))?;
+    decode(path, &text)
+}
+
+pub fn write<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    let text: String = encode(path, value)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, text)
+}
+
+pub fn parse<P: Params>(path: &Path, text: &str) -> Result<Config<P>, String> {
+    decode(path, text)
+}
+
+pub fn to_string<P: Params>(
+    path: &Path,
+    config: &Config<P>,
+) -> Result<String, String> {
+    encode(path, config)
+}
+
+pub fn load<P: Params>(path: &Path) -> Result<Config<P>, String> {
+    read(path)
+}
+
+pub fn save<P: Params>(path: &Path, config: &Config<P>) -> io::Result<()> {
+    write(path, config)
+}
+
+/* NOTE: Polled rather than pushed; one `stat` per call is cheap enough to
+ * do every frame.
+ */
+pub struct Watch {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+impl Watch {
+    pub fn new(path: PathBuf) -> Self {
+        let modified: Option<SystemTime> = modified(&path);
+        Self { path, modified }
+    }
+
+    pub fn changed(&mut self) -> bool {
+        let modified: Option<SystemTime> = modified(&self.path);
+        if modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        modified.is_some()
+    }
+}
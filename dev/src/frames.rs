@@ -0,0 +1,157 @@
+use crate::raster::Raster;
+use graphics::Context;
+use png::{BitDepth, ColorType, Encoder, Writer};
+use std::ffi::OsStr;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn write_png(
+    path: &Path,
+    width: u32,
+    height: u32,
+    pixels: &[u8],
+) -> io::Result<()> {
+    write_png_dpi(path, width, height, pixels, None)
+}
+
+/* NOTE: The resolution goes in a `pHYs` chunk, which PNG measures in
+ * pixels per metre.
+ */
+pub fn write_png_dpi(
+    path: &Path,
+    width: u32,
+    height: u32,
+    pixels: &[u8],
+    dpi: Option<f64>,
+) -> io::Result<()> {
+    let mut encoder: Encoder<BufWriter<File>> =
+        Encoder::new(BufWriter::new(File::create(path)?), width, height);
+    encoder.set_color(ColorType::RGBA);
+    encoder.set_depth(BitDepth::Eight);
+    let mut writer: Writer<BufWriter<File>> = encoder.write_header()?;
+    if let Some(dpi) = dpi {
+        let pixels_per_metre: [u8; 4] =
+            ((dpi / 0.0254).round() as u32).to_be_bytes();
+        let mut phys: Vec<u8> = Vec::with_capacity(9);
+        phys.extend_from_slice(&pixels_per_metre);
+        phys.extend_from_slice(&pixels_per_metre);
+        phys.push(1);
+        writer.write_chunk(*b"pHYs", &phys)?;
+    }
+    writer.write_image_data(pixels)?;
+    Ok(())
+}
+
+pub fn write_raster(path: &Path, raster: &Raster) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    write_png(path, raster.width(), raster.height(), raster.pixels())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Png,
+    Svg,
+}
+
+impl Format {
+    pub fn of(path: &Path) -> Result<Self, String> {
+        match path.extension().and_then(OsStr::to_str) {
+            Some("png") => Ok(Self::Png),
+            Some("svg") => Ok(Self::Svg),
+            _ => Err(format!("`{}` is not a .png or .svg", path.display())),
+        }
+    }
+}
+
+/* NOTE: A macro rather than a function, since `$render` has to be
+ * instantiated once for each kind of canvas.
+ */
+#[macro_export]
+macro_rules! write_image {
+    ($path:expr, $print:expr, |$canvas:ident| $render:expr $(,)?) => {{
+        let path: &std::path::Path = $path;
+        let print: &$crate::print::Print = $print;
+        match $crate::frames::Format::of(path) {
+            Ok($crate::frames::Format::Png) => {
+                $crate::print::write_png(path, print, |$canvas| $render)
+            }
+            Ok($crate::frames::Format::Svg) => {
+                let mut svg: $crate::svg::Svg =
+                    $crate::svg::Svg::new(print.view[0], print.view[1]);
+                let [width, height]: [String; 2] = print.svg_size();
+                svg.set_size(width, height);
+                {
+                    let $canvas = &mut svg;
+                    $render
+                }
+                svg.write(path)
+            }
+            Err(error) => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                error,
+            )),
+        }
+    }};
+}
+
+pub fn snapshot_path(dir: &Path, name: &str, extension: &str) -> PathBuf {
+    let millis: u128 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis());
+    dir.join(format!("{}-{}.{}", name, millis, extension))
+}
+
+/* NOTE: Frames are numbered from zero and zero-padded, so the files sort in
+ * order and match an `%06d.png` pattern.
+ */
+pub struct Frames {
+    dir: PathBuf,
+    raster: Raster,
+    index: u32,
+}
+
+impl Frames {
+    pub fn new(
+        dir: PathBuf,
+        width: u32,
+        height: u32,
+        samples: u8,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            raster: Raster::new(width, height, samples),
+            index: 0,
+        })
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn path(&self, index: u32) -> PathBuf {
+        self.dir.join(format!("{:06}.png", index))
+    }
+
+    pub fn write<F>(&mut self, f: F) -> io::Result<PathBuf>
+    where
+        F: FnOnce(Context, &mut Raster),
+    {
+        self.raster.draw(f);
+        let path: PathBuf = self.path(self.index);
+        write_png(
+            &path,
+            self.raster.width(),
+            self.raster.height(),
+            self.raster.pixels(),
+        )?;
+        self.index += 1;
+        Ok(path)
+    }
+}
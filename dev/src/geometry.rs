@@ -0,0 +1,303 @@
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, Sub};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    pub const fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    pub fn scale(self, k: f32) -> Self {
+        Self {
+            x: self.x * k,
+            y: self.y * k,
+        }
+    }
+
+    pub fn dot(self, other: Self) -> f32 {
+        (self.x * other.x) + (self.y * other.y)
+    }
+
+    pub fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(self) -> Self {
+        let length: f32 = self.length();
+        if length == 0.0 {
+            self
+        } else {
+            self.scale(1.0 / length)
+        }
+    }
+
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self).scale(t)
+    }
+}
+
+impl Add for Point {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            x: self.x + other.x,
+            y: self.y + other.y,
+        }
+    }
+}
+
+impl Sub for Point {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self {
+            x: self.x - other.x,
+            y: self.y - other.y,
+        }
+    }
+}
+
+pub trait Coordinates: Copy + PartialEq {
+    const DIMENSION: usize;
+
+    fn splat(value: f32) -> Self;
+    fn get(&self, axis: usize) -> f32;
+    fn set(&mut self, axis: usize, value: f32);
+}
+
+impl Coordinates for Point {
+    const DIMENSION: usize = 2;
+
+    fn splat(value: f32) -> Self {
+        Self::new(value, value)
+    }
+
+    fn get(&self, axis: usize) -> f32 {
+        if axis == 0 {
+            self.x
+        } else {
+            self.y
+        }
+    }
+
+    fn set(&mut self, axis: usize, value: f32) {
+        if axis == 0 {
+            self.x = value;
+        } else {
+            self.y = value;
+        }
+    }
+}
+
+impl<const D: usize> Coordinates for [f32; D] {
+    const DIMENSION: usize = D;
+
+    fn splat(value: f32) -> Self {
+        [value; D]
+    }
+
+    fn get(&self, axis: usize) -> f32 {
+        self[axis]
+    }
+
+    fn set(&mut self, axis: usize, value: f32) {
+        self[axis] = value;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds<P = Point> {
+    pub lower: P,
+    pub upper: P,
+}
+
+impl Bounds {
+    pub const fn new(
+        lower_x: f32,
+        lower_y: f32,
+        upper_x: f32,
+        upper_y: f32,
+    ) -> Self {
+        Self {
+            lower: Point::new(lower_x, lower_y),
+            upper: Point::new(upper_x, upper_y),
+        }
+    }
+}
+
+impl<P: Coordinates> Default for Bounds<P> {
+    fn default() -> Self {
+        Self {
+            lower: P::splat(0.0),
+            upper: P::splat(0.0),
+        }
+    }
+}
+
+impl<P: Coordinates> Bounds<P> {
+    pub fn contains(&self, point: &P) -> bool {
+        (0..P::DIMENSION).all(|axis| {
+            let value: f32 = point.get(axis);
+            (self.lower.get(axis) <= value) && (value <= self.upper.get(axis))
+        })
+    }
+
+    pub fn include(mut self, point: &P) -> Self {
+        for axis in 0..P::DIMENSION {
+            let value: f32 = point.get(axis);
+            self.lower.set(axis, self.lower.get(axis).min(value));
+            self.upper.set(axis, self.upper.get(axis).max(value));
+        }
+        self
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        (0..P::DIMENSION).all(|axis| {
+            (self.lower.get(axis) <= other.upper.get(axis))
+                && (other.lower.get(axis) <= self.upper.get(axis))
+        })
+    }
+
+    pub fn wrap(&self, point: &P) -> P {
+        let mut point: P = *point;
+        for axis in 0..P::DIMENSION {
+            let lower: f32 = self.lower.get(axis);
+            let size: f32 = self.upper.get(axis) - lower;
+            point
+                .set(axis, lower + (point.get(axis) - lower).rem_euclid(size));
+        }
+        point
+    }
+
+    pub fn nearest_image(&self, point: &P, other: &P) -> P {
+        let mut other: P = *other;
+        for axis in 0..P::DIMENSION {
+            let size: f32 = self.upper.get(axis) - self.lower.get(axis);
+            let delta: f32 = other.get(axis) - point.get(axis);
+            other.set(
+                axis,
+                point.get(axis) + (delta - (size * (delta / size).round())),
+            );
+        }
+        other
+    }
+
+    pub fn half_period(&self) -> f32 {
+        (0..P::DIMENSION)
+            .map(|axis| (self.upper.get(axis) - self.lower.get(axis)) / 2.0)
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    pub fn clip(&self, a: &P, b: &P) -> Option<(P, P)> {
+        let mut t_lower: f32 = 0.0;
+        let mut t_upper: f32 = 1.0;
+        for axis in 0..P::DIMENSION {
+            let start: f32 = a.get(axis);
+            let delta: f32 = b.get(axis) - start;
+            let lower: f32 = self.lower.get(axis);
+            let upper: f32 = self.upper.get(axis);
+            if delta == 0.0 {
+                if (start < lower) || (upper < start) {
+                    return None;
+                }
+            } else {
+                let t_0: f32 = (lower - start) / delta;
+                let t_1: f32 = (upper - start) / delta;
+                t_lower = t_lower.max(t_0.min(t_1));
+                t_upper = t_upper.min(t_0.max(t_1));
+                if t_upper < t_lower {
+                    return None;
+                }
+            }
+        }
+        /* NOTE: Ends that need no clipping are returned untouched, so
+         * segments that shared an end still share it exactly.
+         */
+        let at = |t: f32| -> P {
+            let mut point: P = *a;
+            for axis in 0..P::DIMENSION {
+                let start: f32 = a.get(axis);
+                point.set(axis, start + ((b.get(axis) - start) * t));
+            }
+            point
+        };
+        Some((
+            if t_lower == 0.0 { *a } else { at(t_lower) },
+            if t_upper == 1.0 { *b } else { at(t_upper) },
+        ))
+    }
+}
+
+pub fn squared_distance<P: Coordinates>(a: &P, b: &P) -> f32 {
+    let mut distance: f32 = 0.0;
+    for axis in 0..P::DIMENSION {
+        let x: f32 = a.get(axis) - b.get(axis);
+        distance += x * x;
+    }
+    distance
+}
+
+pub fn bounds_to_point_squared_distance<P: Coordinates>(
+    bounds: &Bounds<P>,
+    point: &P,
+) -> f32 {
+    let mut distance: f32 = 0.0;
+    for axis in 0..P::DIMENSION {
+        let value: f32 = point.get(axis);
+        let x: f32 = value
+            - bounds
+                .lower
+                .get(axis)
+                .max(value.min(bounds.upper.get(axis)));
+        distance += x * x;
+    }
+    distance
+}
+
+pub fn periodic_squared_distance<P: Coordinates>(
+    a: &P,
+    b: &P,
+    period: &Bounds<P>,
+) -> f32 {
+    squared_distance(a, &period.nearest_image(a, b))
+}
+
+pub fn for_each_image<P: Coordinates>(
+    point: &P,
+    reach: f32,
+    period: &Bounds<P>,
+    mut f: impl FnMut(&P),
+) {
+    /* NOTE: Every axis either keeps `point` where it is or shifts it by one
+     * period towards whichever face lies within `reach`, which walks the
+     * `3^DIMENSION` neighbouring tiles and skips the ones out of range.
+     */
+    let n: usize = (0..P::DIMENSION).fold(1, |n, _| n * 3);
+    'images: for i in 0..n {
+        let mut image: P = *point;
+        let mut digits: usize = i;
+        for axis in 0..P::DIMENSION {
+            let value: f32 = point.get(axis);
+            let lower: f32 = period.lower.get(axis);
+            let upper: f32 = period.upper.get(axis);
+            match digits % 3 {
+                0 => (),
+                1 if (value - reach) < lower => {
+                    image.set(axis, value + (upper - lower));
+                }
+                2 if upper < (value + reach) => {
+                    image.set(axis, value - (upper - lower));
+                }
+                _ => continue 'images,
+            }
+            digits /= 3;
+        }
+        f(&image);
+    }
+}
@@ -0,0 +1,260 @@
+#![allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_possible_wrap,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+
+use crate::geometry::{squared_distance, Bounds, Point};
+use crate::kdtree_lib::{Neighbor, Storage};
+use crate::neighbor::NeighborIndex;
+
+#[derive(Clone, Copy, Default)]
+pub struct Entry {
+    pub point: Point,
+    pub index: usize,
+}
+
+pub struct Grid {
+    cell_size: f32,
+    bounds: Bounds,
+    columns: usize,
+    rows: usize,
+    buckets: Vec<Vec<Entry>>,
+    len: usize,
+}
+
+impl Grid {
+    pub fn new(cell_size: f32) -> Self {
+        assert!(0.0 < cell_size);
+        Self {
+            cell_size,
+            bounds: Bounds::default(),
+            columns: 1,
+            rows: 1,
+            buckets: vec![Vec::new()],
+            len: 0,
+        }
+    }
+
+    pub fn build(points: &[Point], bounds: Bounds, cell_size: f32) -> Self {
+        let mut grid: Self = Self::new(cell_size);
+        grid.rebuild(points, bounds);
+        grid
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /* NOTE: A zero, negative or `NaN` cell would size the grid at
+     * `usize::MAX` columns, so those are refused up front.
+     */
+    pub fn set_cell_size(&mut self, cell_size: f32) {
+        assert!(0.0 < cell_size);
+        self.cell_size = cell_size;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn column(&self, x: f32) -> usize {
+        let column: f32 = (x - self.bounds.lower.x) / self.cell_size;
+        (column.max(0.0) as usize).min(self.columns - 1)
+    }
+
+    fn row(&self, y: f32) -> usize {
+        let row: f32 = (y - self.bounds.lower.y) / self.cell_size;
+        (row.max(0.0) as usize).min(self.rows - 1)
+    }
+
+    fn bucket(&self, point: &Point) -> usize {
+        (self.row(point.y) * self.columns) + self.column(point.x)
+    }
+
+    pub fn rebuild(&mut self, points: &[Point], bounds: Bounds) {
+        self.bounds = points
+            .iter()
+            .fold(bounds, |bounds, point| bounds.include(point));
+        self.columns = (((self.bounds.upper.x - self.bounds.lower.x)
+            / self.cell_size) as usize)
+            + 1;
+        self.rows = (((self.bounds.upper.y - self.bounds.lower.y)
+            / self.cell_size) as usize)
+            + 1;
+        for bucket in &mut self.buckets {
+            bucket.clear();
+        }
+        self.buckets.resize_with(self.columns * self.rows, Vec::new);
+        for (index, point) in points.iter().enumerate() {
+            let bucket: usize = self.bucket(point);
+            self.buckets[bucket].push(Entry {
+                point: *point,
+                index,
+            });
+        }
+        self.len = points.len();
+    }
+
+    pub fn insert(&mut self, index: usize, point: Point) {
+        /* NOTE: Points outside of the grid are clamped into its edge buckets,
+         * which keeps queries exact without having to re-bucket everything.
+         */
+        let bucket: usize = self.bucket(&point);
+        self.buckets[bucket].push(Entry { point, index });
+        self.len += 1;
+    }
+
+    pub fn remove(&mut self, index: usize, point: &Point) -> bool {
+        let bucket: usize = self.bucket(point);
+        let entries: &mut Vec<Entry> = &mut self.buckets[bucket];
+        if let Some(i) = entries.iter().position(|entry| entry.index == index)
+        {
+            entries.swap_remove(i);
+            self.len -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn update(&mut self, index: usize, from: &Point, to: Point) -> bool {
+        let bucket: usize = self.bucket(from);
+        if bucket == self.bucket(&to) {
+            if let Some(entry) = self.buckets[bucket]
+                .iter_mut()
+                .find(|entry| entry.index == index)
+            {
+                entry.point = to;
+                return true;
+            }
+            false
+        } else if self.remove(index, from) {
+            self.insert(index, to);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn search(
+        &self,
+        point: &Point,
+        radius_squared: f32,
+        neighbors: &mut impl Storage<usize>,
+    ) {
+        let radius: f32 = radius_squared.sqrt();
+        let (column_lower, column_upper): (usize, usize) =
+            (self.column(point.x - radius), self.column(point.x + radius));
+        let (row_lower, row_upper): (usize, usize) =
+            (self.row(point.y - radius), self.row(point.y + radius));
+        for row in row_lower..=row_upper {
+            for column in column_lower..=column_upper {
+                for entry in &self.buckets[(row * self.columns) + column] {
+                    let neighbor: &Point = &entry.point;
+                    if (point != neighbor)
+                        && (squared_distance(point, neighbor) < radius_squared)
+                    {
+                        neighbors.push(entry.index);
+                    }
+                }
+            }
+        }
+    }
+
+    fn nearest_bucket(
+        &self,
+        row: isize,
+        column: isize,
+        point: &Point,
+        nearest: &mut Option<Neighbor>,
+    ) {
+        let bucket: usize =
+            ((row as usize) * self.columns) + (column as usize);
+        for entry in &self.buckets[bucket] {
+            let squared_distance: f32 = squared_distance(point, &entry.point);
+            if (!squared_distance.is_nan())
+                && nearest.is_none_or(|nearest| {
+                    squared_distance < nearest.squared_distance
+                })
+            {
+                *nearest = Some(Neighbor {
+                    index: entry.index,
+                    squared_distance,
+                });
+            }
+        }
+    }
+
+    pub fn nearest(&self, point: &Point) -> Option<Neighbor> {
+        /* NOTE: Buckets are visited in square rings around the bucket of
+         * `point`; everything past ring `k` is at least `k * cell_size`
+         * away, so the walk stops once the best candidate is closer than
+         * that.
+         */
+        let column: isize = self.column(point.x) as isize;
+        let row: isize = self.row(point.y) as isize;
+        let columns: isize = self.columns as isize;
+        let rows: isize = self.rows as isize;
+        let mut nearest: Option<Neighbor> = None;
+        if self.is_empty() {
+            return nearest;
+        }
+        for ring in 0..columns.max(rows) {
+            for r in (row - ring).max(0)..=(row + ring).min(rows - 1) {
+                if (r - row).abs() == ring {
+                    for c in (column - ring).max(0)
+                        ..=(column + ring).min(columns - 1)
+                    {
+                        self.nearest_bucket(r, c, point, &mut nearest);
+                    }
+                } else {
+                    for c in [column - ring, column + ring] {
+                        if (0 <= c) && (c < columns) {
+                            self.nearest_bucket(r, c, point, &mut nearest);
+                        }
+                    }
+                }
+            }
+            if let Some(nearest) = nearest {
+                let reach: f32 = (ring as f32) * self.cell_size;
+                if nearest.squared_distance <= (reach * reach) {
+                    break;
+                }
+            }
+        }
+        nearest
+    }
+}
+
+impl NeighborIndex for Grid {
+    fn rebuild(&mut self, points: &[Point], bounds: Bounds) {
+        Grid::rebuild(self, points, bounds);
+    }
+
+    fn insert(&mut self, index: usize, point: Point) {
+        Grid::insert(self, index, point);
+    }
+
+    fn update(&mut self, index: usize, from: &Point, to: Point) -> bool {
+        Grid::update(self, index, from, to)
+    }
+
+    fn search(
+        &self,
+        point: &Point,
+        radius_squared: f32,
+        neighbors: &mut impl Storage<usize>,
+    ) {
+        Grid::search(self, point, radius_squared, neighbors);
+    }
+
+    fn nearest(&self, point: &Point) -> Option<Neighbor> {
+        Grid::nearest(self, point)
+    }
+}
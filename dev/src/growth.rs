@@ -2,108 +2,380 @@
 
 mod growth_lib;
 
-use arrayvec::ArrayVec;
-use graphics::math::Matrix2d;
-use graphics::Transformed;
-use growth_lib::{Node, Point};
+use growth_lib::{Node, Params};
 use opengl_graphics::{GlGraphics, OpenGL};
 use piston::event_loop::{EventSettings, Events};
-use piston::input::{RenderArgs, RenderEvent};
+use piston::input::{Button, Key, PressEvent, RenderArgs, RenderEvent};
 use piston::window::WindowSettings;
 use rand::distributions::Uniform;
-use rand::rngs::ThreadRng;
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+use ranim_core::args::Args;
+use ranim_core::batch;
+use ranim_core::canvas::{Canvas, GraphicsCanvas, Style};
+use ranim_core::config;
+use ranim_core::config::{Config, Watch};
+use ranim_core::frames;
+use ranim_core::frames::Frames;
+use ranim_core::geometry::{Bounds, Point};
+use ranim_core::kdtree_lib::KdTree;
+use ranim_core::params;
+use ranim_core::plot;
+use ranim_core::plot::{Paper, Stroke};
+use ranim_core::raster::Raster;
+use ranim_core::replay::{Input, Session};
+use ranim_core::snapshot;
+use ranim_core::snapshot::Snapshot;
+use ranim_core::svg::Svg;
 use sdl2_window::Sdl2Window;
 use std::io;
 use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process;
 use std::time::Instant;
 
-fn render(gl: &mut GlGraphics, args: &RenderArgs, nodes: &[Node]) {
-    gl.draw(args.viewport(), |context, gl| {
-        let [width, height]: [f64; 2] = args.window_size;
-        let transform: Matrix2d =
-            context.transform.trans(width / 2.0, height / 2.0);
-        graphics::clear(growth_lib::DARK_GRAY, gl);
-        {
-            let node: &Node = nodes.last().unwrap();
-            let x: f64 = node.point.x as f64;
-            let y: f64 = node.point.y as f64;
-            graphics::ellipse(
-                growth_lib::CYAN,
-                [
-                    x - growth_lib::RADIUS_2,
-                    y - growth_lib::RADIUS_2,
-                    growth_lib::RADIUS_4,
-                    growth_lib::RADIUS_4,
-                ],
-                transform,
-                gl,
-            );
+macro_rules! vertex {
+    ($point:expr $(,)?) => {
+        [$point.x as f64, $point.y as f64]
+    };
+}
+
+fn segments(nodes: &[Node], wrap: Option<&Bounds>) -> Vec<(Point, Point)> {
+    let mut segments: Vec<(Point, Point)> = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        let left: &Point = &nodes[node.left_index].point;
+        if let Some(bounds) = wrap {
+            let image: Point = bounds.nearest_image(&node.point, left);
+            segments.push((node.point, image));
+            if image != *left {
+                segments
+                    .push((bounds.nearest_image(left, &node.point), *left));
+            }
+        } else {
+            segments.push((node.point, *left));
         }
-        for node in nodes {
-            let x: f64 = node.point.x as f64;
-            let y: f64 = node.point.y as f64;
-            graphics::ellipse(
-                growth_lib::LIGHT_GRAY,
-                [
-                    x - growth_lib::RADIUS,
-                    y - growth_lib::RADIUS,
-                    growth_lib::RADIUS_2,
-                    growth_lib::RADIUS_2,
-                ],
-                transform,
-                gl,
-            );
-            let left: &Point = &nodes[node.left_index].point;
-            let left_x: f64 = left.x as f64;
-            let left_y: f64 = left.y as f64;
-            graphics::line(
-                growth_lib::LIGHT_GRAY,
-                growth_lib::LINE_WIDTH,
-                [left_x, left_y, x, y],
-                transform,
-                gl,
-            )
+    }
+    segments
+}
+
+fn strokes(nodes: &[Node], wrap: Option<&Bounds>) -> Vec<Vec<[f64; 2]>> {
+    /* NOTE: The curve is walked once around through `left_index`; when
+     * wrapping, it is broken into a new stroke wherever it crosses the
+     * border, and each side of the tile gets its half of that edge.
+     */
+    let mut strokes: Vec<Vec<[f64; 2]>> = vec![Vec::new()];
+    let mut index: usize = 0;
+    for _ in 0..nodes.len() {
+        let node: &Node = &nodes[index];
+        let left: &Point = &nodes[node.left_index].point;
+        let image: Point = match wrap {
+            Some(bounds) => bounds.nearest_image(&node.point, left),
+            None => *left,
+        };
+        let stroke: &mut Vec<[f64; 2]> = strokes.last_mut().unwrap();
+        if stroke.is_empty() {
+            stroke.push(vertex!(node.point));
+        }
+        stroke.push(vertex!(image));
+        if image != *left {
+            let bounds: &Bounds = wrap.unwrap();
+            strokes.push(vec![
+                vertex!(bounds.nearest_image(left, &node.point)),
+                vertex!(left),
+            ]);
+        }
+        index = node.left_index;
+        if index == 0 {
+            break;
         }
-    })
+    }
+    strokes
+}
+
+fn render(canvas: &mut impl Canvas, nodes: &[Node], params: &Params) {
+    canvas.clear(params.dark_gray);
+    {
+        let node: &Node = nodes.last().unwrap();
+        canvas.circle(
+            &Style::fill(params.cyan),
+            vertex!(node.point),
+            params.radius * 2.0,
+        );
+    }
+    let edge: Style =
+        Style::stroke(params.light_gray, params.line_width * 2.0);
+    for stroke in &strokes(nodes, params.wrap_bounds().as_ref()) {
+        canvas.polyline(&edge, stroke);
+    }
+    let node: Style = Style::fill(params.light_gray);
+    for point in nodes.iter().map(|node| node.point) {
+        canvas.circle(&node, vertex!(point), params.radius);
+    }
+}
+
+fn simulate(seed: u64, steps: u64, params: &Params) -> Vec<Node> {
+    let mut rng: Pcg64 = Pcg64::seed_from_u64(seed);
+    let point_rng_upper: f32 = params.point_rng_upper();
+    let uniform_init: Uniform<f32> =
+        Uniform::new_inclusive(-point_rng_upper, point_rng_upper);
+    let uniform_walk: Uniform<f32> =
+        Uniform::new_inclusive(-params.walk_rng, params.walk_rng);
+    let mut tree: KdTree = KdTree::new();
+    tree.set_rebalance_threshold(Some(params.rebalance_threshold));
+    let mut nodes: Vec<Node> = Vec::with_capacity(params.capacity);
+    growth_lib::init_nodes(
+        &mut rng,
+        uniform_init,
+        &mut nodes,
+        &mut tree,
+        params,
+    );
+    for _ in 0..steps {
+        growth_lib::step(
+            &mut rng,
+            uniform_init,
+            uniform_walk,
+            &mut nodes,
+            &mut tree,
+            params,
+        );
+    }
+    nodes
 }
 
 fn main() {
+    let mut params: Params = Params::default();
+    let mut args: Args = Args::from_env(&mut params);
+    let restored: Option<Snapshot<Params, Vec<Node>>> =
+        args.restore(&mut params);
+    let mut session: Session<Params> = args.session(&mut params);
+    if let Some(plan) = args.batch(&params, |params| {
+        (params.window_edge as u32, params.window_edge as u32)
+    }) {
+        let colors: [[f32; 4]; 2] = [params.dark_gray, params.light_gray];
+        let result: io::Result<PathBuf> =
+            batch::run(&plan, "growth", colors, |tile| {
+                let edge: u32 = tile.params.window_edge as u32;
+                let nodes: Vec<Node> =
+                    simulate(tile.seed, plan.steps, &tile.params);
+                let mut raster: Raster =
+                    Raster::new(edge, edge, tile.params.anti_alias);
+                raster.draw(|context, raster| {
+                    render(
+                        &mut GraphicsCanvas::new(context, raster),
+                        &nodes,
+                        &tile.params,
+                    )
+                });
+                raster
+            });
+        match result {
+            Ok(path) => println!("{}", path.display()),
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(1)
+            }
+        }
+        return;
+    }
+    let seed: u64 = restored
+        .as_ref()
+        .map_or_else(|| args.seed(), |snapshot| snapshot.seed);
+    println!("seed {}", seed);
+    print!("{}", params::describe(&params));
+    let steps: Option<u64> = args.steps;
+    let svg_dir: PathBuf =
+        args.svg.clone().unwrap_or_else(|| PathBuf::from("."));
+    let plot_dir: PathBuf =
+        args.plot.clone().unwrap_or_else(|| PathBuf::from("."));
+    let paper: Paper = args.paper;
+    let mut export: Option<Frames> = args.frames.clone().map(|dir| {
+        Frames::new(
+            dir,
+            params.window_edge as u32,
+            params.window_edge as u32,
+            params.anti_alias,
+        )
+        .unwrap()
+    });
+    let point_rng_upper: f32 = params.point_rng_upper();
+    let uniform_init: Uniform<f32> =
+        Uniform::new_inclusive(-point_rng_upper, point_rng_upper);
+    let mut uniform_walk: Uniform<f32> =
+        Uniform::new_inclusive(-params.walk_rng, params.walk_rng);
+    let mut tree: KdTree = KdTree::new();
+    tree.set_rebalance_threshold(Some(params.rebalance_threshold));
+    let (mut rng, mut nodes, mut step): (Pcg64, Vec<Node>, u64) =
+        match restored {
+            Some(snapshot) => {
+                growth_lib::index_nodes(&snapshot.state, &mut tree, &params);
+                (snapshot.rng, snapshot.state, snapshot.step)
+            }
+            None => {
+                let mut rng: Pcg64 = Pcg64::seed_from_u64(seed);
+                let mut nodes: Vec<Node> = Vec::with_capacity(params.capacity);
+                growth_lib::init_nodes(
+                    &mut rng,
+                    uniform_init,
+                    &mut nodes,
+                    &mut tree,
+                    &params,
+                );
+                (rng, nodes, 0)
+            }
+        };
+    if let Some(out) = &args.out {
+        while steps.is_some_and(|steps| step < steps) {
+            growth_lib::step(
+                &mut rng,
+                uniform_init,
+                uniform_walk,
+                &mut nodes,
+                &mut tree,
+                &params,
+            );
+            step += 1;
+        }
+        ranim_core::write_image!(
+            out,
+            &args.print(
+                params.window_edge,
+                params.window_edge,
+                params.anti_alias,
+            ),
+            |canvas| render(canvas, &nodes, &params),
+        )
+        .unwrap();
+        println!("{}", out.display());
+        return;
+    }
     let opengl: OpenGL = OpenGL::V3_2;
-    let mut window: Sdl2Window = WindowSettings::new(
-        "ranim",
-        [growth_lib::WINDOW_EDGE, growth_lib::WINDOW_EDGE],
-    )
-    .graphics_api(opengl)
-    .exit_on_esc(true)
-    .samples(growth_lib::ANTI_ALIAS)
-    .vsync(true)
-    .build()
-    .unwrap();
+    let mut window: Sdl2Window =
+        WindowSettings::new("ranim", [params.window_edge, params.window_edge])
+            .graphics_api(opengl)
+            .exit_on_esc(true)
+            .samples(params.anti_alias)
+            .vsync(true)
+            .build()
+            .unwrap();
     let mut events: Events = Events::new(EventSettings::new());
     let mut gl: GlGraphics = GlGraphics::new(opengl);
-    let mut rng: ThreadRng = rand::thread_rng();
-    let uniform_init: Uniform<f32> = Uniform::new_inclusive(
-        growth_lib::POINT_RNG_LOWER,
-        growth_lib::POINT_RNG_UPPER,
-    );
-    let uniform_walk: Uniform<f32> = Uniform::new_inclusive(
-        growth_lib::WALK_RNG_LOWER,
-        growth_lib::WALK_RNG_UPPER,
-    );
-    let mut nodes: ArrayVec<[Node; growth_lib::CAPACITY]> = ArrayVec::new();
-    growth_lib::init_nodes(&mut rng, uniform_init, &mut nodes);
+    let mut watch: Option<Watch> = args
+        .config
+        .clone()
+        .filter(|_| !session.is_replay())
+        .map(Watch::new);
     let mut frames: u16 = 0;
     let mut elapsed: f32 = 0.0;
     let mut clock: Instant = Instant::now();
     while let Some(event) = events.next(&mut window) {
-        if let Some(args) = event.render_args() {
-            if growth_lib::NODES_CAP_LIMIT < nodes.len() {
-                nodes.clear();
-                growth_lib::init_nodes(&mut rng, uniform_init, &mut nodes);
-            } else {
-                growth_lib::update_nodes(&mut rng, uniform_walk, &mut nodes);
+        let mut live: Vec<Input<Params>> = Vec::new();
+        if watch.as_mut().is_some_and(Watch::changed) {
+            let mut reloaded: Params = Params::default();
+            match args.apply(&mut reloaded) {
+                Ok(()) => live.push(Input::Reload(reloaded)),
+                Err(error) => eprintln!("{}", error),
+            }
+        }
+        if let Some(button) = event.press_args() {
+            live.push(Input::Press(button));
+        }
+        let render_args: Option<RenderArgs> = event.render_args();
+        for input in session.inputs(step, live, render_args.is_some()) {
+            match input {
+                Input::Reload(reloaded) => {
+                    params.reload(reloaded);
+                    uniform_walk = Uniform::new_inclusive(
+                        -params.walk_rng,
+                        params.walk_rng,
+                    );
+                    tree.set_rebalance_threshold(Some(
+                        params.rebalance_threshold,
+                    ));
+                    print!("{}", params::describe(&params));
+                }
+                Input::Press(Button::Keyboard(Key::W)) => {
+                    params.wrap = !params.wrap
+                }
+                Input::Press(Button::Keyboard(Key::C)) => {
+                    let path: PathBuf = frames::snapshot_path(
+                        Path::new("."),
+                        "growth",
+                        "toml",
+                    );
+                    let config: Config<Params> = Config {
+                        seed: Some(seed),
+                        steps,
+                        params: params.clone(),
+                    };
+                    config::save(&path, &config).unwrap();
+                    println!("{}", path.display());
+                }
+                Input::Press(Button::Keyboard(Key::K)) => {
+                    let path: PathBuf = frames::snapshot_path(
+                        Path::new("."),
+                        "growth",
+                        "json",
+                    );
+                    let snapshot: Snapshot<Params, &[Node]> = Snapshot {
+                        seed,
+                        step,
+                        rng: rng.clone(),
+                        params: params.clone(),
+                        state: &nodes,
+                    };
+                    snapshot::save(&path, &snapshot).unwrap();
+                    println!("{}", path.display());
+                }
+                Input::Press(Button::Keyboard(Key::S)) => {
+                    let path: PathBuf =
+                        frames::snapshot_path(&svg_dir, "growth", "svg");
+                    let mut svg: Svg =
+                        Svg::new(params.window_edge, params.window_edge);
+                    render(&mut svg, &nodes, &params);
+                    svg.write(&path).unwrap();
+                    println!("{}", path.display());
+                }
+                Input::Press(Button::Keyboard(Key::P)) => {
+                    let strokes: Vec<Stroke> = plot::strokes(
+                        &segments(&nodes, params.wrap_bounds().as_ref()),
+                        &params.bounds(),
+                        &paper,
+                    );
+                    let (hpgl, gcode): (PathBuf, PathBuf) =
+                        plot::write(&plot_dir, "growth", &strokes).unwrap();
+                    println!("{}\n{}", hpgl.display(), gcode.display());
+                }
+                _ => (),
+            }
+        }
+        if let Some(args) = render_args {
+            growth_lib::step(
+                &mut rng,
+                uniform_init,
+                uniform_walk,
+                &mut nodes,
+                &mut tree,
+                &params,
+            );
+            gl.draw(args.viewport(), |context, gl| {
+                render(&mut GraphicsCanvas::new(context, gl), &nodes, &params)
+            });
+            if let Some(export) = &mut export {
+                export
+                    .write(|context, raster| {
+                        render(
+                            &mut GraphicsCanvas::new(context, raster),
+                            &nodes,
+                            &params,
+                        )
+                    })
+                    .unwrap();
+            }
+            step += 1;
+            if steps.is_some_and(|steps| steps <= step) {
+                break;
             }
-            render(&mut gl, &args, &nodes);
             frames += 1;
             elapsed += clock.elapsed().as_secs_f32();
             clock = Instant::now();
@@ -115,5 +387,8 @@ fn main() {
             };
         }
     }
-    println!()
+    println!();
+    if let Some(path) = session.finish(step).unwrap() {
+        println!("{}", path.display());
+    }
 }
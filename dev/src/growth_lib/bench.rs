@@ -5,30 +5,47 @@ extern crate bencher;
 
 mod r#mod;
 
-use arrayvec::ArrayVec;
 use bencher::Bencher;
-use r#mod::Node;
+use r#mod::{Node, Params};
 use rand::distributions::Uniform;
-use rand::rngs::ThreadRng;
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+use ranim_core::grid::Grid;
+use ranim_core::kdtree_lib::KdTree;
+use ranim_core::neighbor::NeighborIndex;
+
+const SEED: u64 = 0;
+
+fn run_nodes(index: &mut impl NeighborIndex, params: &Params) {
+    let mut rng: Pcg64 = Pcg64::seed_from_u64(SEED);
+    let point_rng_upper: f32 = params.point_rng_upper();
+    let uniform_init: Uniform<f32> =
+        Uniform::new_inclusive(-point_rng_upper, point_rng_upper);
+    let uniform_walk: Uniform<f32> =
+        Uniform::new_inclusive(-params.walk_rng, params.walk_rng);
+    let mut nodes: Vec<Node> = Vec::with_capacity(params.capacity);
+    r#mod::init_nodes(&mut rng, uniform_init, &mut nodes, index, params);
+    for _ in 0..params.capacity {
+        r#mod::update_nodes(&mut rng, uniform_walk, &mut nodes, index, params);
+    }
+}
 
 fn init_update_nodes(b: &mut Bencher) {
+    let params: Params = Params::default();
+    b.iter(|| {
+        let mut tree: KdTree = KdTree::new();
+        tree.set_rebalance_threshold(Some(params.rebalance_threshold));
+        run_nodes(&mut tree, &params);
+    })
+}
+
+fn init_update_nodes_grid(b: &mut Bencher) {
+    let params: Params = Params::default();
     b.iter(|| {
-        let mut rng: ThreadRng = rand::thread_rng();
-        let uniform_init: Uniform<f32> = Uniform::new_inclusive(
-            r#mod::POINT_RNG_LOWER,
-            r#mod::POINT_RNG_UPPER,
-        );
-        let uniform_walk: Uniform<f32> = Uniform::new_inclusive(
-            r#mod::WALK_RNG_LOWER,
-            r#mod::WALK_RNG_UPPER,
-        );
-        let mut nodes: ArrayVec<[Node; r#mod::CAPACITY]> = ArrayVec::new();
-        r#mod::init_nodes(&mut rng, uniform_init, &mut nodes);
-        for _ in 0..r#mod::CAPACITY {
-            r#mod::update_nodes(&mut rng, uniform_walk, &mut nodes);
-        }
+        let mut grid: Grid = Grid::new(params.search_radius_squared.sqrt());
+        run_nodes(&mut grid, &params);
     })
 }
 
-benchmark_group!(benches, init_update_nodes);
+benchmark_group!(benches, init_update_nodes, init_update_nodes_grid);
 benchmark_main!(benches);
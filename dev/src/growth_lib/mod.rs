@@ -1,205 +1,126 @@
 #![allow(clippy::cast_possible_truncation)]
 
-use arrayvec::ArrayVec;
 use rand::distributions::Uniform;
-use rand::rngs::ThreadRng;
 use rand::Rng;
+use ranim_core::geometry::{squared_distance, Bounds, Point};
+use ranim_core::neighbor::NeighborIndex;
+use ranim_core::raster::SAMPLES_MAX;
+use serde::{Deserialize, Serialize};
 
-pub const WINDOW_EDGE: f64 = 800.0;
-const WINDOW_EDGE_HALF: f32 = (WINDOW_EDGE as f32) / 2.0;
-const WINDOW_EDGE_HALF_MINUS: f32 = -WINDOW_EDGE_HALF;
+const WINDOW_EDGE: f64 = 800.0;
 
-pub const ANTI_ALIAS: u8 = 4;
+const ANTI_ALIAS: u8 = 4;
 
-pub const LIGHT_GRAY: [f32; 4] = [0.95, 0.95, 0.95, 1.0];
-pub const DARK_GRAY: [f32; 4] = [0.15, 0.15, 0.15, 1.0];
-pub const CYAN: [f32; 4] = [0.5, 1.0, 0.87, 0.5];
+const LIGHT_GRAY: [f32; 4] = [0.95, 0.95, 0.95, 1.0];
+const DARK_GRAY: [f32; 4] = [0.15, 0.15, 0.15, 1.0];
+const CYAN: [f32; 4] = [0.5, 1.0, 0.87, 0.5];
 
-pub const LINE_WIDTH: f64 = 1.15;
-pub const RADIUS: f64 = 4.0;
-pub const RADIUS_2: f64 = RADIUS * 2.0;
-pub const RADIUS_4: f64 = RADIUS * 4.0;
+const LINE_WIDTH: f64 = 1.15;
+const RADIUS: f64 = 4.0;
 
-pub const CAPACITY: usize = 512;
-pub const NODES_CAP_LIMIT: usize = CAPACITY - 1;
+const CAPACITY: usize = 512;
 const NODES_INIT: usize = 3;
 const NODES_INIT_LIMIT: usize = NODES_INIT - 1;
 
-pub const POINT_RNG_UPPER: f32 = WINDOW_EDGE_HALF / 3.0;
-pub const POINT_RNG_LOWER: f32 = -POINT_RNG_UPPER;
-pub const WALK_RNG_UPPER: f32 = 0.15;
-pub const WALK_RNG_LOWER: f32 = -WALK_RNG_UPPER;
+const WALK_RNG_UPPER: f32 = 0.15;
 
 const NEIGHBOR_RADIUS_SQUARED: f32 = 1000.0;
 const SEARCH_RADIUS_SQUARED: f32 = 2000.0;
 
-const DRAG_ATTRACT: f32 = 35.0;
-const DRAG_REJECT: f32 = 25.0;
+const WRAP: bool = false;
 
-const BOUNDS: Bounds = Bounds {
-    lower: Point {
-        x: WINDOW_EDGE_HALF_MINUS as f32,
-        y: WINDOW_EDGE_HALF_MINUS as f32,
-    },
-    upper: Point {
-        x: WINDOW_EDGE_HALF as f32,
-        y: WINDOW_EDGE_HALF as f32,
-    },
-};
-
-#[derive(Clone, PartialEq)]
-pub struct Point {
-    pub x: f32,
-    pub y: f32,
-}
+const REBALANCE_THRESHOLD: f32 = 0.5;
 
-type NodeIndex = usize;
+const DRAG_ATTRACT: f32 = 35.0;
+const DRAG_REJECT: f32 = 25.0;
 
-pub struct Node {
-    pub point: Point,
-    pub left_index: NodeIndex,
-    right_index: NodeIndex,
+/* NOTE: Both drags divide a node's pull toward its target, so below 1 it
+ * overshoots and the line blows up.
+ */
+ranim_core::params! {
+    pub struct Params {
+        #[range(1.0..)]
+        window_edge: f64 = WINDOW_EDGE,
+        #[range(1..=SAMPLES_MAX)]
+        anti_alias: u8 = ANTI_ALIAS,
+        light_gray: [f32; 4] = LIGHT_GRAY,
+        dark_gray: [f32; 4] = DARK_GRAY,
+        cyan: [f32; 4] = CYAN,
+        #[range(0.0..)]
+        line_width: f64 = LINE_WIDTH,
+        #[range(0.0..)]
+        radius: f64 = RADIUS,
+        #[range(NODES_INIT..)]
+        capacity: usize = CAPACITY,
+        #[range(0.0..)]
+        walk_rng: f32 = WALK_RNG_UPPER,
+        #[range(1.0..)]
+        drag_attract: f32 = DRAG_ATTRACT,
+        #[range(1.0..)]
+        drag_reject: f32 = DRAG_REJECT,
+        #[range(0.0..)]
+        neighbor_radius_squared: f32 = NEIGHBOR_RADIUS_SQUARED,
+        #[range(0.0..)]
+        search_radius_squared: f32 = SEARCH_RADIUS_SQUARED,
+        #[range(0.0..)]
+        rebalance_threshold: f32 = REBALANCE_THRESHOLD,
+        wrap: bool = WRAP,
+    }
 }
 
-pub struct Bounds {
-    pub lower: Point,
-    pub upper: Point,
-}
+impl Params {
+    pub fn bounds(&self) -> Bounds {
+        let half: f32 = (self.window_edge as f32) / 2.0;
+        Bounds::new(-half, -half, half, half)
+    }
 
-macro_rules! make_bounds {
-    ($lower_x:expr, $lower_y:expr, $upper_x:expr, $upper_y: expr $(,)?) => {
-        Bounds {
-            lower: Point {
-                x: $lower_x,
-                y: $lower_y,
-            },
-            upper: Point {
-                x: $upper_x,
-                y: $upper_y,
-            },
+    pub fn wrap_bounds(&self) -> Option<Bounds> {
+        if self.wrap {
+            Some(self.bounds())
+        } else {
+            None
         }
-    };
-}
+    }
 
-type TreeIndex = usize;
+    /* NOTE: Everything but the window can change while running. */
+    pub fn reload(&mut self, params: Self) {
+        *self = Self {
+            window_edge: self.window_edge,
+            anti_alias: self.anti_alias,
+            ..params
+        };
+    }
 
-struct Tree {
-    point: Point,
-    bounds: Bounds,
-    left_index: Option<TreeIndex>,
-    right_index: Option<TreeIndex>,
+    pub fn point_rng_upper(&self) -> f32 {
+        (self.window_edge as f32) / 6.0
+    }
 }
 
-fn make_tree(
-    trees: &mut ArrayVec<[Tree; CAPACITY]>,
-    points: &mut [Point],
-    horizontal: bool,
-    bounds: Bounds,
-) -> Option<TreeIndex> {
-    let n: usize = points.len();
-    if n == 0 {
-        return None;
-    }
-    let median: usize = n / 2;
-    let lower_x: f32 = bounds.lower.x;
-    let lower_y: f32 = bounds.lower.y;
-    let upper_x: f32 = bounds.upper.x;
-    let upper_y: f32 = bounds.upper.y;
-    let (point, horizontal, left_bounds, right_bounds): (
-        Point,
-        bool,
-        Bounds,
-        Bounds,
-    ) = {
-        if horizontal {
-            pdqselect::select_by(points, median, |a, b| {
-                a.x.partial_cmp(&b.x).unwrap()
-            });
-            let point: Point = points[median].clone();
-            let x: f32 = point.x;
-            (
-                point,
-                false,
-                make_bounds!(lower_x, lower_y, x, upper_y),
-                make_bounds!(x, lower_y, upper_x, upper_y),
-            )
+type NodeIndex = usize;
+
+macro_rules! image {
+    ($point:expr, $other:expr, $bounds:expr $(,)?) => {
+        if let Some(bounds) = $bounds {
+            bounds.nearest_image($point, $other)
         } else {
-            pdqselect::select_by(points, median, |a, b| {
-                a.y.partial_cmp(&b.y).unwrap()
-            });
-            let point: Point = points[median].clone();
-            let y: f32 = point.y;
-            (
-                point,
-                true,
-                make_bounds!(lower_x, lower_y, upper_x, y),
-                make_bounds!(lower_x, y, upper_x, upper_y),
-            )
+            *$other
         }
     };
-    let left_index: Option<TreeIndex> =
-        make_tree(trees, &mut points[..median], horizontal, left_bounds);
-    let right_index: Option<TreeIndex> = make_tree(
-        trees,
-        &mut points[(median + 1)..],
-        horizontal,
-        right_bounds,
-    );
-    trees.push(Tree {
-        point,
-        bounds,
-        left_index,
-        right_index,
-    });
-    Some(trees.len() - 1)
 }
 
-fn squared_distance(a: &Point, b: &Point) -> f32 {
-    let x: f32 = a.x - b.x;
-    let y: f32 = a.y - b.y;
-    (x * x) + (y * y)
-}
-
-fn bounds_to_point_squared_distance(bounds: &Bounds, point: &Point) -> f32 {
-    let x: f32 = point.x - bounds.lower.x.max(point.x.min(bounds.upper.x));
-    let y: f32 = point.y - bounds.lower.y.max(point.y.min(bounds.upper.y));
-    (x * x) + (y * y)
-}
-
-fn search_trees(
-    point: &Point,
-    trees: &ArrayVec<[Tree; CAPACITY]>,
-    init_index: TreeIndex,
-    neighbors: &mut ArrayVec<[TreeIndex; CAPACITY]>,
-) {
-    let mut stack: ArrayVec<[TreeIndex; CAPACITY]> = ArrayVec::new();
-    stack.push(init_index);
-    while !stack.is_empty() {
-        let index: TreeIndex = stack.pop().unwrap();
-        let tree: &Tree = &trees[index];
-        if bounds_to_point_squared_distance(&tree.bounds, point)
-            < SEARCH_RADIUS_SQUARED
-        {
-            let neighbor: &Point = &tree.point;
-            if (point != neighbor)
-                && (squared_distance(point, neighbor) < SEARCH_RADIUS_SQUARED)
-            {
-                neighbors.push(index);
-            }
-            if let Some(left_index) = tree.left_index {
-                stack.push(left_index);
-            }
-            if let Some(right_index) = tree.right_index {
-                stack.push(right_index);
-            }
-        }
-    }
+#[derive(Serialize, Deserialize)]
+pub struct Node {
+    pub point: Point,
+    pub left_index: NodeIndex,
+    right_index: NodeIndex,
 }
 
 pub fn init_nodes(
-    rng: &mut ThreadRng,
+    rng: &mut impl Rng,
     uniform: Uniform<f32>,
-    nodes: &mut ArrayVec<[Node; CAPACITY]>,
+    nodes: &mut Vec<Node>,
+    index: &mut impl NeighborIndex,
+    params: &Params,
 ) {
     for i in 0..NODES_INIT {
         let (left_index, right_index): (NodeIndex, NodeIndex) = {
@@ -220,94 +141,145 @@ pub fn init_nodes(
             right_index,
         });
     }
+    index_nodes(nodes, index, params);
 }
 
-fn insert_node(nodes: &mut ArrayVec<[Node; CAPACITY]>, left_index: NodeIndex) {
+pub fn index_nodes(
+    nodes: &[Node],
+    index: &mut impl NeighborIndex,
+    params: &Params,
+) {
+    let points: Vec<Point> = nodes.iter().map(|node| node.point).collect();
+    index.rebuild(&points, params.bounds());
+}
+
+fn insert_node(
+    nodes: &mut Vec<Node>,
+    left_index: NodeIndex,
+    bounds: Option<&Bounds>,
+) -> NodeIndex {
     let index: usize = nodes.len();
     let right_index: NodeIndex = nodes[left_index].right_index;
-    let left_point: &Point = &nodes[left_index].point;
-    let right_point: &Point = &nodes[right_index].point;
-    let left_x: f32 = left_point.x;
-    let left_y: f32 = left_point.y;
-    let right_x: f32 = right_point.x;
-    let right_y: f32 = right_point.y;
+    let left_point: Point = nodes[left_index].point;
+    let right_point: Point =
+        image!(&left_point, &nodes[right_index].point, bounds);
+    let mut point: Point = left_point.lerp(right_point, 0.5);
+    if let Some(bounds) = bounds {
+        point = bounds.wrap(&point);
+    }
     nodes.push(Node {
-        point: Point {
-            x: (left_x + right_x) / 2.0,
-            y: (left_y + right_y) / 2.0,
-        },
+        point,
         left_index,
         right_index,
     });
     nodes[left_index].right_index = index;
     nodes[right_index].left_index = index;
+    index
 }
 
 #[allow(clippy::cast_precision_loss)]
 pub fn update_nodes(
-    rng: &mut ThreadRng,
+    rng: &mut impl Rng,
     uniform: Uniform<f32>,
-    nodes: &mut ArrayVec<[Node; CAPACITY]>,
+    nodes: &mut Vec<Node>,
+    index: &mut impl NeighborIndex,
+    params: &Params,
 ) {
-    for node in nodes.iter_mut() {
+    let wrap: Option<Bounds> = params.wrap_bounds();
+    let bounds: Option<&Bounds> = wrap.as_ref();
+    for (i, node) in nodes.iter_mut().enumerate() {
+        let from: Point = node.point;
         node.point.x += rng.sample(uniform);
         node.point.y += rng.sample(uniform);
+        if let Some(bounds) = bounds {
+            node.point = bounds.wrap(&node.point);
+        }
+        index.update(i, &from, node.point);
     }
-    let mut index: Option<usize> = None;
+    let mut split: Option<usize> = None;
     for i in 0..nodes.len() {
-        if NEIGHBOR_RADIUS_SQUARED
-            < squared_distance(
-                &nodes[i].point,
-                &nodes[nodes[i].right_index].point,
-            )
+        let point: &Point = &nodes[i].point;
+        let right_point: Point =
+            image!(point, &nodes[nodes[i].right_index].point, bounds);
+        if params.neighbor_radius_squared
+            < squared_distance(point, &right_point)
         {
-            index = Some(i);
+            split = Some(i);
             break;
         }
     }
-    if let Some(i) = index {
-        insert_node(nodes, i);
-    }
-    let mut points: ArrayVec<[Point; CAPACITY]> = ArrayVec::new();
-    for node in nodes.iter() {
-        points.push(node.point.clone());
+    if let Some(i) = split {
+        let node_index: NodeIndex = insert_node(nodes, i, bounds);
+        index.insert(node_index, nodes[node_index].point);
     }
-    let mut trees: ArrayVec<[Tree; CAPACITY]> = ArrayVec::new();
-    let mut neighbors: ArrayVec<[TreeIndex; CAPACITY]> = ArrayVec::new();
-    if let Some(index) = make_tree(&mut trees, &mut points, true, BOUNDS) {
-        let mut next_points: ArrayVec<[(usize, Point); CAPACITY]> =
-            ArrayVec::new();
-        for (i, node) in nodes.iter().enumerate() {
-            let point: &Point = &node.point;
-            let left_point: &Point = &nodes[node.left_index].point;
-            let right_point: &Point = &nodes[node.right_index].point;
-            let mut next_point: Point = Point {
-                x: point.x
-                    + ((((left_point.x + right_point.x) / 2.0) - point.x)
-                        / DRAG_ATTRACT),
-                y: point.y
-                    + ((((left_point.y + right_point.y) / 2.0) - point.y)
-                        / DRAG_ATTRACT),
-            };
-            search_trees(point, &trees, index, &mut neighbors);
-            let n: usize = neighbors.len();
-            if n != 0 {
-                let mut x: f32 = 0.0;
-                let mut y: f32 = 0.0;
-                for neighbor_index in neighbors.drain(..n) {
-                    let neighbor_point: &Point = &trees[neighbor_index].point;
-                    x += point.x - neighbor_point.x;
-                    y += point.y - neighbor_point.y;
-                }
-                let n: f32 = n as f32;
-                next_point.x += (x / n) / DRAG_REJECT;
-                next_point.y += (y / n) / DRAG_REJECT;
+    let mut neighbors: Vec<usize> = Vec::with_capacity(nodes.len());
+    let mut next_points: Vec<(usize, Point)> = Vec::with_capacity(nodes.len());
+    for (i, node) in nodes.iter().enumerate() {
+        let point: &Point = &node.point;
+        let left_point: Point =
+            image!(point, &nodes[node.left_index].point, bounds);
+        let right_point: Point =
+            image!(point, &nodes[node.right_index].point, bounds);
+        let mut next_point: Point = Point {
+            x: point.x
+                + ((((left_point.x + right_point.x) / 2.0) - point.x)
+                    / params.drag_attract),
+            y: point.y
+                + ((((left_point.y + right_point.y) / 2.0) - point.y)
+                    / params.drag_attract),
+        };
+        if let Some(bounds) = bounds {
+            index.search_periodic(
+                point,
+                params.search_radius_squared,
+                bounds,
+                &mut neighbors,
+            );
+        } else {
+            index.search(point, params.search_radius_squared, &mut neighbors);
+        }
+        /* NOTE: The displacements are summed in `f32`, so they are summed in
+         * index order; search order depends on the shape of the index, which
+         * differs between a restored tree and one updated all along.
+         */
+        neighbors.sort_unstable();
+        let n: usize = neighbors.len();
+        if n != 0 {
+            let mut x: f32 = 0.0;
+            let mut y: f32 = 0.0;
+            for neighbor_index in neighbors.drain(..n) {
+                let neighbor_point: Point =
+                    image!(point, &nodes[neighbor_index].point, bounds);
+                x += point.x - neighbor_point.x;
+                y += point.y - neighbor_point.y;
             }
-            next_points.push((i, next_point));
+            let n: f32 = n as f32;
+            next_point.x += (x / n) / params.drag_reject;
+            next_point.y += (y / n) / params.drag_reject;
         }
-        for (i, next_point) in next_points {
-            nodes[i].point.x = next_point.x;
-            nodes[i].point.y = next_point.y;
+        if let Some(bounds) = bounds {
+            next_point = bounds.wrap(&next_point);
         }
+        next_points.push((i, next_point));
+    }
+    for (i, next_point) in next_points {
+        index.update(i, &nodes[i].point, next_point);
+        nodes[i].point = next_point;
+    }
+}
+
+pub fn step(
+    rng: &mut impl Rng,
+    uniform_init: Uniform<f32>,
+    uniform_walk: Uniform<f32>,
+    nodes: &mut Vec<Node>,
+    index: &mut impl NeighborIndex,
+    params: &Params,
+) {
+    if params.capacity <= nodes.len() {
+        nodes.clear();
+        init_nodes(rng, uniform_init, nodes, index, params);
+    } else {
+        update_nodes(rng, uniform_walk, nodes, index, params);
     }
 }
@@ -1,112 +1,118 @@
 #![allow(clippy::cast_lossless)]
 
-mod kdtree_lib;
-
-use arrayvec::ArrayVec;
-use graphics::math::Matrix2d;
-use graphics::Transformed;
-use kdtree_lib::{Bounds, Point, Tree};
 use opengl_graphics::{GlGraphics, OpenGL};
 use piston::event_loop::{EventSettings, Events};
-use piston::input::{RenderArgs, RenderEvent};
+use piston::input::{
+    Button, Key, MouseScrollEvent, PressEvent, RenderArgs, RenderEvent,
+};
 use piston::window::WindowSettings;
 use rand::distributions::Uniform;
-use rand::rngs::ThreadRng;
 use rand::Rng;
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+use ranim_core::args::Args;
+use ranim_core::canvas::{Canvas, GraphicsCanvas, Style};
+use ranim_core::config;
+use ranim_core::config::Config;
+use ranim_core::frames;
+use ranim_core::frames::Frames;
+use ranim_core::geometry::{for_each_image, Bounds, Point};
+use ranim_core::kdtree_lib::{KdTree, Params, Tree};
+use ranim_core::neighbor::NeighborIndex;
+use ranim_core::params;
+use ranim_core::replay::{Input, Session};
 use sdl2_window::Sdl2Window;
+use std::path::{Path, PathBuf};
+use std::process;
 
-unsafe fn render(
-    gl: &mut GlGraphics,
-    args: &RenderArgs,
+fn render(
+    canvas: &mut impl Canvas,
     point: &Point,
+    search_radius: f32,
+    points: &[Point],
     trees: &[Tree],
-    neighbors: &mut ArrayVec<[*const Point; kdtree_lib::CAPACITY]>,
+    neighbors: &[usize],
+    params: &Params,
 ) {
-    gl.draw(args.viewport(), |context, gl| {
-        let [width, height]: [f64; 2] = args.window_size;
-        let transform: Matrix2d =
-            context.transform.trans(width / 2.0, height / 2.0);
-        graphics::clear(kdtree_lib::DARK_GRAY, gl);
-        let n: usize = neighbors.len();
-        for neighbor in neighbors.drain(..n) {
-            graphics::ellipse(
-                kdtree_lib::RED,
-                [
-                    ((*neighbor).x as f64) - kdtree_lib::RADIUS_2,
-                    ((*neighbor).y as f64) - kdtree_lib::RADIUS_2,
-                    kdtree_lib::RADIUS_4,
-                    kdtree_lib::RADIUS_4,
-                ],
-                transform,
-                gl,
-            );
-        }
-        for tree in trees {
-            let point: &Point = &tree.point;
-            let x: f64 = point.x as f64;
-            let y: f64 = point.y as f64;
-            let bounds: &Bounds = &tree.bounds;
-            let line: [f64; 4] = if tree.horizontal {
-                [x, bounds.lower.y as f64, x, bounds.upper.y as f64]
-            } else {
-                [bounds.lower.x as f64, y, bounds.upper.x as f64, y]
-            };
-            graphics::ellipse(
-                kdtree_lib::LIGHT_GRAY,
-                [
-                    x - kdtree_lib::RADIUS,
-                    y - kdtree_lib::RADIUS,
-                    kdtree_lib::RADIUS_2,
-                    kdtree_lib::RADIUS_2,
-                ],
-                transform,
-                gl,
-            );
-            graphics::line(
-                kdtree_lib::LIGHT_GRAY,
-                kdtree_lib::LINE_WIDTH,
-                line,
-                transform,
-                gl,
-            );
-        }
-        graphics::ellipse(
-            kdtree_lib::TEAL,
-            [
-                (point.x - kdtree_lib::SEARCH_RADIUS) as f64,
-                (point.y - kdtree_lib::SEARCH_RADIUS) as f64,
-                kdtree_lib::SEARCH_RADIUS_2 as f64,
-                kdtree_lib::SEARCH_RADIUS_2 as f64,
-            ],
-            transform,
-            gl,
+    canvas.clear(params.dark_gray);
+    let neighbor_style: Style = Style::fill(params.red);
+    for neighbor in neighbors {
+        let neighbor: &Point = &points[*neighbor];
+        canvas.circle(
+            &neighbor_style,
+            [neighbor.x as f64, neighbor.y as f64],
+            params.radius * 2.0,
         );
-    })
+    }
+    let node: Style = Style::fill(params.light_gray);
+    let split: Style =
+        Style::stroke(params.light_gray, params.line_width * 2.0);
+    for tree in trees.iter().filter(|tree| !tree.removed) {
+        let point: &Point = &tree.point;
+        let x: f64 = point.x as f64;
+        let y: f64 = point.y as f64;
+        let bounds: &Bounds = &tree.bounds;
+        let line: [f64; 4] = if tree.axis == 0 {
+            [x, bounds.lower.y as f64, x, bounds.upper.y as f64]
+        } else {
+            [bounds.lower.x as f64, y, bounds.upper.x as f64, y]
+        };
+        canvas.circle(&node, [x, y], params.radius);
+        canvas.line(&split, line);
+    }
+    let search: Style = Style::fill(params.teal);
+    let mut draw_search = |point: &Point| {
+        canvas.circle(
+            &search,
+            [point.x as f64, point.y as f64],
+            search_radius as f64,
+        );
+    };
+    if params.wrap {
+        for_each_image(point, search_radius, &params.bounds(), |image| {
+            draw_search(image)
+        });
+    } else {
+        draw_search(point);
+    }
 }
 
 fn main() {
-    let opengl: OpenGL = OpenGL::V3_2;
-    let mut window: Sdl2Window = WindowSettings::new(
-        "ranim",
-        [kdtree_lib::WINDOW_EDGE, kdtree_lib::WINDOW_EDGE],
-    )
-    .graphics_api(opengl)
-    .exit_on_esc(true)
-    .samples(kdtree_lib::ANTI_ALIAS)
-    .vsync(true)
-    .build()
-    .unwrap();
-    let mut events: Events = Events::new(EventSettings::new());
-    let mut gl: GlGraphics = GlGraphics::new(opengl);
-    let mut rng: ThreadRng = rand::thread_rng();
-    let uniform_init: Uniform<f32> = Uniform::new_inclusive(
-        kdtree_lib::POINT_RNG_LOWER,
-        kdtree_lib::POINT_RNG_UPPER,
-    );
-    let uniform_walk: Uniform<f32> = Uniform::new_inclusive(
-        kdtree_lib::WALK_RNG_LOWER,
-        kdtree_lib::WALK_RNG_UPPER,
-    );
+    let mut params: Params = Params::default();
+    let mut args: Args = Args::from_env(&mut params);
+    if args.batch.is_some() {
+        eprintln!("kdtree has no batch mode");
+        process::exit(2)
+    }
+    if args.restore.is_some() {
+        eprintln!("kdtree has no snapshots to restore");
+        process::exit(2)
+    }
+    if args.svg.is_some() || args.plot.is_some() {
+        eprintln!("kdtree has no svg or plot export");
+        process::exit(2)
+    }
+    let mut session: Session<Params> = args.session(&mut params);
+    let seed: u64 = args.seed();
+    println!("seed {}", seed);
+    print!("{}", params::describe(&params));
+    let steps: Option<u64> = args.steps;
+    let mut export: Option<Frames> = args.frames.clone().map(|dir| {
+        Frames::new(
+            dir,
+            params.window_edge as u32,
+            params.window_edge as u32,
+            params.anti_alias,
+        )
+        .unwrap()
+    });
+    let mut rng: Pcg64 = Pcg64::seed_from_u64(seed);
+    let bounds: Bounds = params.bounds();
+    let point_rng_upper: f32 = params.point_rng_upper();
+    let uniform_init: Uniform<f32> =
+        Uniform::new_inclusive(-point_rng_upper, point_rng_upper);
+    let uniform_walk: Uniform<f32> =
+        Uniform::new_inclusive(-params.walk_rng, params.walk_rng);
     macro_rules! make_point {
         () => {
             Point {
@@ -116,45 +122,172 @@ fn main() {
         };
     }
     let mut point: Point = make_point!();
-    let mut points: ArrayVec<[Point; kdtree_lib::CAPACITY]> = ArrayVec::new();
-    unsafe {
-        for _ in 0..kdtree_lib::CAPACITY {
-            points.push_unchecked(make_point!());
-        }
+    let mut points: Vec<Point> = Vec::with_capacity(params.capacity);
+    for _ in 0..params.capacity {
+        points.push(make_point!());
     }
-    let mut trees: ArrayVec<[Tree; kdtree_lib::CAPACITY]> = ArrayVec::new();
-    let mut neighbors: ArrayVec<[*const Point; kdtree_lib::CAPACITY]> =
-        ArrayVec::new();
+    let mut tree: KdTree = KdTree::new();
+    tree.set_rebalance_threshold(Some(params.rebalance_threshold));
+    tree.rebuild(&points, bounds);
+    let mut neighbors: Vec<usize> = Vec::with_capacity(params.capacity);
+    let mut search_radius: f32 = params.search_radius;
     let mut counter: u16 = 0;
-    while let Some(event) = events.next(&mut window) {
-        if let Some(args) = event.render_args() {
-            if kdtree_lib::RELOAD_FRAME_INTERVAL < counter {
+    let mut step: u64 = 0;
+    macro_rules! step {
+        () => {
+            neighbors.clear();
+            if params.reload_frame_interval < counter {
                 point = make_point!();
-                for i in 0..kdtree_lib::CAPACITY {
-                    points[i].x = rng.sample(uniform_init);
-                    points[i].y = rng.sample(uniform_init);
+                for point in &mut points {
+                    point.x = rng.sample(uniform_init);
+                    point.y = rng.sample(uniform_init);
                 }
+                tree.rebuild(&points, bounds);
                 counter = 0;
             } else {
                 point.x += rng.sample(uniform_walk);
                 point.y += rng.sample(uniform_walk);
-                for point in &mut points {
+                if params.wrap {
+                    point = bounds.wrap(&point);
+                }
+                for (i, point) in points.iter_mut().enumerate() {
+                    let from: Point = *point;
                     point.x += rng.sample(uniform_walk);
                     point.y += rng.sample(uniform_walk);
+                    if params.wrap {
+                        *point = bounds.wrap(point);
+                    }
+                    tree.update(i, &from, *point);
                 }
                 counter += 1;
             }
-            unsafe {
-                let tree: *mut Tree = kdtree_lib::make_tree(
-                    &mut trees,
-                    &mut points,
-                    true,
-                    kdtree_lib::BOUNDS,
+            if params.wrap {
+                tree.search_periodic(
+                    &point,
+                    search_radius * search_radius,
+                    &bounds,
+                    &mut neighbors,
                 );
-                kdtree_lib::search_trees(&point, tree, &mut neighbors);
-                render(&mut gl, &args, &point, &trees, &mut neighbors);
+            } else {
+                tree.search(
+                    &point,
+                    search_radius * search_radius,
+                    &mut neighbors,
+                );
+            }
+        };
+    }
+    if let Some(out) = &args.out {
+        while steps.is_some_and(|steps| step < steps) {
+            step!();
+            step += 1;
+        }
+        ranim_core::write_image!(
+            out,
+            &args.print(
+                params.window_edge,
+                params.window_edge,
+                params.anti_alias,
+            ),
+            |canvas| render(
+                canvas,
+                &point,
+                search_radius,
+                &points,
+                tree.trees(),
+                &neighbors,
+                &params,
+            ),
+        )
+        .unwrap();
+        println!("{}", out.display());
+        return;
+    }
+    let opengl: OpenGL = OpenGL::V3_2;
+    let mut window: Sdl2Window =
+        WindowSettings::new("ranim", [params.window_edge, params.window_edge])
+            .graphics_api(opengl)
+            .exit_on_esc(true)
+            .samples(params.anti_alias)
+            .vsync(true)
+            .build()
+            .unwrap();
+    let mut events: Events = Events::new(EventSettings::new());
+    let mut gl: GlGraphics = GlGraphics::new(opengl);
+    while let Some(event) = events.next(&mut window) {
+        let mut live: Vec<Input<Params>> = Vec::new();
+        if let Some(button) = event.press_args() {
+            live.push(Input::Press(button));
+        }
+        if let Some([_, scroll]) = event.mouse_scroll_args() {
+            live.push(Input::Scroll(scroll));
+        }
+        let render_args: Option<RenderArgs> = event.render_args();
+        for input in session.inputs(step, live, render_args.is_some()) {
+            match input {
+                Input::Press(Button::Keyboard(Key::W)) => {
+                    params.wrap = !params.wrap
+                }
+                Input::Press(Button::Keyboard(Key::C)) => {
+                    let path: PathBuf = frames::snapshot_path(
+                        Path::new("."),
+                        "kdtree",
+                        "toml",
+                    );
+                    let config: Config<Params> = Config {
+                        seed: Some(seed),
+                        steps,
+                        params: Params {
+                            search_radius,
+                            ..params.clone()
+                        },
+                    };
+                    config::save(&path, &config).unwrap();
+                    println!("{}", path.display());
+                }
+                Input::Scroll(scroll) => {
+                    search_radius = (search_radius
+                        + ((scroll as f32) * params.search_radius_step))
+                        .max(params.search_radius_min);
+                }
+                _ => (),
             }
-            trees.clear();
         }
+        if let Some(args) = render_args {
+            step!();
+            gl.draw(args.viewport(), |context, gl| {
+                render(
+                    &mut GraphicsCanvas::new(context, gl),
+                    &point,
+                    search_radius,
+                    &points,
+                    tree.trees(),
+                    &neighbors,
+                    &params,
+                )
+            });
+            if let Some(export) = &mut export {
+                export
+                    .write(|context, raster| {
+                        render(
+                            &mut GraphicsCanvas::new(context, raster),
+                            &point,
+                            search_radius,
+                            &points,
+                            tree.trees(),
+                            &neighbors,
+                            &params,
+                        )
+                    })
+                    .unwrap();
+            }
+            step += 1;
+            if steps.is_some_and(|steps| steps <= step) {
+                break;
+            }
+        }
+    }
+    if let Some(path) = session.finish(step).unwrap() {
+        println!("{}", path.display());
     }
 }
@@ -1,16 +1,18 @@
-#![allow(dead_code)]
-
 #[macro_use]
 extern crate bencher;
 
-mod r#mod;
-
 use arrayvec::ArrayVec;
 use bencher::Bencher;
-use r#mod::{Point, Tree};
 use rand::distributions::Uniform;
 use rand::rngs::ThreadRng;
 use rand::Rng;
+use ranim_core::geometry::{Bounds, Point};
+use ranim_core::grid::Grid;
+use ranim_core::kdtree_lib as r#mod;
+use ranim_core::kdtree_lib::{KdTree, StackKdTree};
+use ranim_core::neighbor::NeighborIndex;
+
+const LARGE_CAPACITY: usize = 100_000;
 
 macro_rules! make_point {
     ($rng:expr, $uniform:expr $(,)?) => {
@@ -21,13 +23,21 @@ macro_rules! make_point {
     };
 }
 
+macro_rules! make_point_3d {
+    ($rng:expr, $uniform:expr $(,)?) => {
+        [
+            $rng.sample($uniform),
+            $rng.sample($uniform),
+            $rng.sample($uniform),
+        ]
+    };
+}
+
 macro_rules! make_points {
-    ($rng:expr, $uniform:expr $(,)?) => {{
-        let mut points: ArrayVec<[Point; r#mod::CAPACITY]> = ArrayVec::new();
-        unsafe {
-            for _ in 0..r#mod::CAPACITY {
-                points.push_unchecked(make_point!($rng, $uniform))
-            }
+    ($rng:expr, $uniform:expr, $n:expr $(,)?) => {{
+        let mut points: Vec<Point> = Vec::with_capacity($n);
+        for _ in 0..$n {
+            points.push(make_point!($rng, $uniform))
         }
         points
     }};
@@ -37,34 +47,265 @@ fn make_tree(b: &mut Bencher) {
     let mut rng: ThreadRng = rand::thread_rng();
     let uniform: Uniform<f32> =
         Uniform::new_inclusive(r#mod::POINT_RNG_LOWER, r#mod::POINT_RNG_UPPER);
-    let mut points: ArrayVec<[Point; r#mod::CAPACITY]> =
-        make_points!(rng, uniform);
+    let points: Vec<Point> = make_points!(rng, uniform, r#mod::CAPACITY);
+    let mut tree: KdTree = KdTree::new();
+    b.iter(|| tree.rebuild(&points, r#mod::BOUNDS))
+}
+
+fn make_tree_stack(b: &mut Bencher) {
+    let mut rng: ThreadRng = rand::thread_rng();
+    let uniform: Uniform<f32> =
+        Uniform::new_inclusive(r#mod::POINT_RNG_LOWER, r#mod::POINT_RNG_UPPER);
+    let points: Vec<Point> = make_points!(rng, uniform, r#mod::CAPACITY);
+    let mut tree: StackKdTree = StackKdTree::new();
+    b.iter(|| tree.rebuild(&points, r#mod::BOUNDS))
+}
+
+fn make_tree_large(b: &mut Bencher) {
+    let mut rng: ThreadRng = rand::thread_rng();
+    let uniform: Uniform<f32> =
+        Uniform::new_inclusive(r#mod::POINT_RNG_LOWER, r#mod::POINT_RNG_UPPER);
+    let points: Vec<Point> = make_points!(rng, uniform, LARGE_CAPACITY);
+    let mut tree: KdTree = KdTree::new();
+    b.iter(|| tree.rebuild(&points, r#mod::BOUNDS))
+}
+
+fn make_grid(b: &mut Bencher) {
+    let mut rng: ThreadRng = rand::thread_rng();
+    let uniform: Uniform<f32> =
+        Uniform::new_inclusive(r#mod::POINT_RNG_LOWER, r#mod::POINT_RNG_UPPER);
+    let points: Vec<Point> = make_points!(rng, uniform, r#mod::CAPACITY);
+    let mut grid: Grid = Grid::new(r#mod::SEARCH_RADIUS);
+    b.iter(|| grid.rebuild(&points, r#mod::BOUNDS))
+}
+
+fn make_grid_large(b: &mut Bencher) {
+    let mut rng: ThreadRng = rand::thread_rng();
+    let uniform: Uniform<f32> =
+        Uniform::new_inclusive(r#mod::POINT_RNG_LOWER, r#mod::POINT_RNG_UPPER);
+    let points: Vec<Point> = make_points!(rng, uniform, LARGE_CAPACITY);
+    let mut grid: Grid = Grid::new(r#mod::SEARCH_RADIUS);
+    b.iter(|| grid.rebuild(&points, r#mod::BOUNDS))
+}
+
+fn update_trees(b: &mut Bencher) {
+    let mut rng: ThreadRng = rand::thread_rng();
+    let uniform: Uniform<f32> =
+        Uniform::new_inclusive(r#mod::POINT_RNG_LOWER, r#mod::POINT_RNG_UPPER);
+    let uniform_walk: Uniform<f32> =
+        Uniform::new_inclusive(r#mod::WALK_RNG_LOWER, r#mod::WALK_RNG_UPPER);
+    let mut points: Vec<Point> = make_points!(rng, uniform, LARGE_CAPACITY);
+    let mut tree: KdTree = KdTree::build(&points, r#mod::BOUNDS);
+    tree.set_rebalance_threshold(Some(r#mod::REBALANCE_THRESHOLD));
     b.iter(|| {
-        let mut trees: ArrayVec<[Tree; r#mod::CAPACITY]> = ArrayVec::new();
-        unsafe {
-            r#mod::make_tree(&mut trees, &mut points, true, r#mod::BOUNDS);
+        for (i, point) in points.iter_mut().enumerate().take(r#mod::CAPACITY) {
+            let from: Point = *point;
+            point.x += rng.sample(uniform_walk);
+            point.y += rng.sample(uniform_walk);
+            tree.update(i, &from, *point);
         }
     })
 }
 
+fn walk_trees_update(b: &mut Bencher) {
+    let mut rng: ThreadRng = rand::thread_rng();
+    let uniform: Uniform<f32> =
+        Uniform::new_inclusive(r#mod::POINT_RNG_LOWER, r#mod::POINT_RNG_UPPER);
+    let uniform_walk: Uniform<f32> =
+        Uniform::new_inclusive(r#mod::WALK_RNG_LOWER, r#mod::WALK_RNG_UPPER);
+    let mut points: Vec<Point> = make_points!(rng, uniform, r#mod::CAPACITY);
+    let mut tree: KdTree = KdTree::build(&points, r#mod::BOUNDS);
+    tree.set_rebalance_threshold(Some(r#mod::REBALANCE_THRESHOLD));
+    b.iter(|| {
+        for (i, point) in points.iter_mut().enumerate() {
+            let from: Point = *point;
+            point.x += rng.sample(uniform_walk);
+            point.y += rng.sample(uniform_walk);
+            tree.update(i, &from, *point);
+        }
+    })
+}
+
+fn walk_trees_rebuild(b: &mut Bencher) {
+    let mut rng: ThreadRng = rand::thread_rng();
+    let uniform: Uniform<f32> =
+        Uniform::new_inclusive(r#mod::POINT_RNG_LOWER, r#mod::POINT_RNG_UPPER);
+    let uniform_walk: Uniform<f32> =
+        Uniform::new_inclusive(r#mod::WALK_RNG_LOWER, r#mod::WALK_RNG_UPPER);
+    let mut points: Vec<Point> = make_points!(rng, uniform, r#mod::CAPACITY);
+    let mut tree: KdTree = KdTree::build(&points, r#mod::BOUNDS);
+    b.iter(|| {
+        for point in points.iter_mut() {
+            point.x += rng.sample(uniform_walk);
+            point.y += rng.sample(uniform_walk);
+        }
+        tree.rebuild(&points, r#mod::BOUNDS)
+    })
+}
+
 fn search_trees(b: &mut Bencher) {
     let mut rng: ThreadRng = rand::thread_rng();
     let uniform: Uniform<f32> =
         Uniform::new_inclusive(r#mod::POINT_RNG_LOWER, r#mod::POINT_RNG_UPPER);
-    let mut points: ArrayVec<[Point; r#mod::CAPACITY]> =
-        make_points!(rng, uniform);
+    let points: Vec<Point> = make_points!(rng, uniform, r#mod::CAPACITY);
+    let point: Point = make_point!(rng, uniform);
+    let tree: KdTree = KdTree::build(&points, r#mod::BOUNDS);
+    let mut neighbors: Vec<usize> = Vec::with_capacity(r#mod::CAPACITY);
+    b.iter(|| {
+        neighbors.clear();
+        tree.search(&point, r#mod::SEARCH_RADIUS_SQUARED, &mut neighbors)
+    })
+}
+
+fn search_trees_stack(b: &mut Bencher) {
+    let mut rng: ThreadRng = rand::thread_rng();
+    let uniform: Uniform<f32> =
+        Uniform::new_inclusive(r#mod::POINT_RNG_LOWER, r#mod::POINT_RNG_UPPER);
+    let points: Vec<Point> = make_points!(rng, uniform, r#mod::CAPACITY);
+    let point: Point = make_point!(rng, uniform);
+    let tree: StackKdTree = StackKdTree::build(&points, r#mod::BOUNDS);
+    let mut neighbors: ArrayVec<[usize; r#mod::CAPACITY]> = ArrayVec::new();
+    b.iter(|| {
+        neighbors.clear();
+        tree.search(&point, r#mod::SEARCH_RADIUS_SQUARED, &mut neighbors)
+    })
+}
+
+fn search_trees_large(b: &mut Bencher) {
+    let mut rng: ThreadRng = rand::thread_rng();
+    let uniform: Uniform<f32> =
+        Uniform::new_inclusive(r#mod::POINT_RNG_LOWER, r#mod::POINT_RNG_UPPER);
+    let points: Vec<Point> = make_points!(rng, uniform, LARGE_CAPACITY);
+    let point: Point = make_point!(rng, uniform);
+    let tree: KdTree = KdTree::build(&points, r#mod::BOUNDS);
+    let mut neighbors: Vec<usize> = Vec::with_capacity(LARGE_CAPACITY);
+    b.iter(|| {
+        neighbors.clear();
+        tree.search(&point, r#mod::SEARCH_RADIUS_SQUARED, &mut neighbors)
+    })
+}
+
+fn search_index<T: NeighborIndex>(b: &mut Bencher, mut index: T, n: usize) {
+    let mut rng: ThreadRng = rand::thread_rng();
+    let uniform: Uniform<f32> =
+        Uniform::new_inclusive(r#mod::POINT_RNG_LOWER, r#mod::POINT_RNG_UPPER);
+    let points: Vec<Point> = make_points!(rng, uniform, n);
+    let point: Point = make_point!(rng, uniform);
+    index.rebuild(&points, r#mod::BOUNDS);
+    let mut neighbors: Vec<usize> = Vec::with_capacity(n);
+    b.iter(|| {
+        neighbors.clear();
+        index.search(&point, r#mod::SEARCH_RADIUS_SQUARED, &mut neighbors)
+    })
+}
+
+fn search_index_tree(b: &mut Bencher) {
+    let tree: KdTree = KdTree::new();
+    search_index(b, tree, r#mod::CAPACITY)
+}
+
+fn search_index_tree_large(b: &mut Bencher) {
+    let tree: KdTree = KdTree::new();
+    search_index(b, tree, LARGE_CAPACITY)
+}
+
+fn search_index_grid(b: &mut Bencher) {
+    let grid: Grid = Grid::new(r#mod::SEARCH_RADIUS);
+    search_index(b, grid, r#mod::CAPACITY)
+}
+
+fn search_index_grid_large(b: &mut Bencher) {
+    let grid: Grid = Grid::new(r#mod::SEARCH_RADIUS);
+    search_index(b, grid, LARGE_CAPACITY)
+}
+
+fn within(b: &mut Bencher) {
+    let mut rng: ThreadRng = rand::thread_rng();
+    let uniform: Uniform<f32> =
+        Uniform::new_inclusive(r#mod::POINT_RNG_LOWER, r#mod::POINT_RNG_UPPER);
+    let points: Vec<Point> = make_points!(rng, uniform, r#mod::CAPACITY);
+    let point: Point = make_point!(rng, uniform);
+    let bounds: Bounds = Bounds::new(
+        point.x - r#mod::SEARCH_RADIUS,
+        point.y - r#mod::SEARCH_RADIUS,
+        point.x + r#mod::SEARCH_RADIUS,
+        point.y + r#mod::SEARCH_RADIUS,
+    );
+    let tree: KdTree = KdTree::build(&points, r#mod::BOUNDS);
+    let mut neighbors: Vec<usize> = Vec::with_capacity(r#mod::CAPACITY);
+    b.iter(|| {
+        neighbors.clear();
+        tree.within(&bounds, &mut neighbors)
+    })
+}
+
+fn nearest(b: &mut Bencher) {
+    let mut rng: ThreadRng = rand::thread_rng();
+    let uniform: Uniform<f32> =
+        Uniform::new_inclusive(r#mod::POINT_RNG_LOWER, r#mod::POINT_RNG_UPPER);
+    let points: Vec<Point> = make_points!(rng, uniform, r#mod::CAPACITY);
+    let point: Point = make_point!(rng, uniform);
+    let tree: KdTree = KdTree::build(&points, r#mod::BOUNDS);
+    b.iter(|| tree.nearest(&point))
+}
+
+fn nearest_large(b: &mut Bencher) {
+    let mut rng: ThreadRng = rand::thread_rng();
+    let uniform: Uniform<f32> =
+        Uniform::new_inclusive(r#mod::POINT_RNG_LOWER, r#mod::POINT_RNG_UPPER);
+    let points: Vec<Point> = make_points!(rng, uniform, LARGE_CAPACITY);
     let point: Point = make_point!(rng, uniform);
-    let mut trees: ArrayVec<[Tree; r#mod::CAPACITY]> = ArrayVec::new();
-    unsafe {
-        let tree: *mut Tree =
-            r#mod::make_tree(&mut trees, &mut points, true, r#mod::BOUNDS);
-        b.iter(|| {
-            let mut neighbors: ArrayVec<[*const Point; r#mod::CAPACITY]> =
-                ArrayVec::new();
-            r#mod::search_trees(&point, tree, &mut neighbors)
-        })
-    }
-}
-
-benchmark_group!(benches, make_tree, search_trees);
+    let tree: KdTree = KdTree::build(&points, r#mod::BOUNDS);
+    b.iter(|| tree.nearest(&point))
+}
+
+fn k_nearest(b: &mut Bencher) {
+    let mut rng: ThreadRng = rand::thread_rng();
+    let uniform: Uniform<f32> =
+        Uniform::new_inclusive(r#mod::POINT_RNG_LOWER, r#mod::POINT_RNG_UPPER);
+    let points: Vec<Point> = make_points!(rng, uniform, r#mod::CAPACITY);
+    let point: Point = make_point!(rng, uniform);
+    let tree: KdTree = KdTree::build(&points, r#mod::BOUNDS);
+    b.iter(|| tree.k_nearest(&point, 8))
+}
+
+fn nearest_3d(b: &mut Bencher) {
+    let mut rng: ThreadRng = rand::thread_rng();
+    let uniform: Uniform<f32> =
+        Uniform::new_inclusive(r#mod::POINT_RNG_LOWER, r#mod::POINT_RNG_UPPER);
+    let points: Vec<[f32; 3]> = (0..LARGE_CAPACITY)
+        .map(|_| make_point_3d!(rng, uniform))
+        .collect();
+    let point: [f32; 3] = make_point_3d!(rng, uniform);
+    let bounds: Bounds<[f32; 3]> = Bounds {
+        lower: [r#mod::POINT_RNG_LOWER; 3],
+        upper: [r#mod::POINT_RNG_UPPER; 3],
+    };
+    let tree: KdTree<[f32; 3]> = KdTree::build(&points, bounds);
+    b.iter(|| tree.nearest(&point))
+}
+
+benchmark_group!(
+    benches,
+    make_tree,
+    make_tree_stack,
+    make_tree_large,
+    make_grid,
+    make_grid_large,
+    update_trees,
+    walk_trees_update,
+    walk_trees_rebuild,
+    search_trees,
+    search_trees_stack,
+    search_trees_large,
+    search_index_tree,
+    search_index_tree_large,
+    search_index_grid,
+    search_index_grid_large,
+    within,
+    nearest,
+    nearest_large,
+    k_nearest,
+    nearest_3d
+);
 benchmark_main!(benches);
@@ -1,8 +1,14 @@
 #![allow(clippy::cast_possible_truncation)]
 
-use arrayvec::ArrayVec;
-use std::ptr;
-use std::slice;
+use crate::geometry::{
+    bounds_to_point_squared_distance, squared_distance, Bounds, Coordinates,
+    Point,
+};
+use crate::neighbor::NeighborIndex;
+use crate::raster::SAMPLES_MAX;
+use arrayvec::{Array, ArrayVec};
+use std::cmp::Ordering;
+use std::ops::DerefMut;
 
 pub const WINDOW_EDGE: f64 = 800.0;
 const WINDOW_EDGE_HALF: f32 = (WINDOW_EDGE as f32) / 2.0;
@@ -16,6 +22,7 @@ pub const RED: [f32; 4] = [0.92, 0.47, 0.47, 0.75];
 pub const TEAL: [f32; 4] = [0.17, 0.82, 0.76, 0.15];
 
 pub const LINE_WIDTH: f64 = 1.15;
+pub const LINE_WIDTH_2: f64 = LINE_WIDTH * 2.0;
 pub const RADIUS: f64 = 6.0;
 pub const RADIUS_2: f64 = RADIUS * 2.0;
 pub const RADIUS_4: f64 = RADIUS * 4.0;
@@ -23,10 +30,18 @@ pub const RADIUS_4: f64 = RADIUS * 4.0;
 pub const RELOAD_FRAME_INTERVAL: u16 = 60 * 8;
 
 pub const CAPACITY: usize = 100;
+pub const TREES_CAP: usize = CAPACITY * 2;
+
+pub const REBALANCE_THRESHOLD: f32 = 0.5;
+
+const DEPTH_FACTOR: usize = 2;
 
 pub const SEARCH_RADIUS: f32 = 150.0;
-pub const SEARCH_RADIUS_2: f32 = SEARCH_RADIUS * 2.0;
-const SEARCH_RADIUS_SQUARED: f32 = SEARCH_RADIUS * SEARCH_RADIUS;
+pub const SEARCH_RADIUS_SQUARED: f32 = SEARCH_RADIUS * SEARCH_RADIUS;
+pub const SEARCH_RADIUS_MIN: f32 = 10.0;
+pub const SEARCH_RADIUS_STEP: f32 = 10.0;
+
+pub const WRAP: bool = false;
 
 pub const POINT_RNG_UPPER: f32 = WINDOW_EDGE_HALF - 50.0;
 pub const POINT_RNG_LOWER: f32 = -POINT_RNG_UPPER;
@@ -44,187 +59,716 @@ pub const BOUNDS: Bounds = Bounds {
     },
 };
 
-#[derive(Clone, PartialEq)]
-pub struct Point {
-    pub x: f32,
-    pub y: f32,
+crate::params! {
+    pub struct Params {
+        #[range(100.0..)]
+        window_edge: f64 = WINDOW_EDGE,
+        #[range(1..=SAMPLES_MAX)]
+        anti_alias: u8 = ANTI_ALIAS,
+        light_gray: [f32; 4] = LIGHT_GRAY,
+        dark_gray: [f32; 4] = DARK_GRAY,
+        red: [f32; 4] = RED,
+        teal: [f32; 4] = TEAL,
+        #[range(0.0..)]
+        line_width: f64 = LINE_WIDTH,
+        #[range(0.0..)]
+        radius: f64 = RADIUS,
+        #[range(1..)]
+        capacity: usize = CAPACITY,
+        #[range(0.0..)]
+        walk_rng: f32 = WALK_RNG_UPPER,
+        #[range(0.0..)]
+        search_radius: f32 = SEARCH_RADIUS,
+        #[range(0.0..)]
+        search_radius_min: f32 = SEARCH_RADIUS_MIN,
+        #[range(0.0..)]
+        search_radius_step: f32 = SEARCH_RADIUS_STEP,
+        #[range(0.0..)]
+        rebalance_threshold: f32 = REBALANCE_THRESHOLD,
+        #[range(..u16::MAX)]
+        reload_frame_interval: u16 = RELOAD_FRAME_INTERVAL,
+        wrap: bool = WRAP,
+    }
 }
 
-pub struct Bounds {
-    pub lower: Point,
-    pub upper: Point,
+impl Params {
+    pub fn bounds(&self) -> Bounds {
+        let half: f32 = (self.window_edge as f32) / 2.0;
+        Bounds::new(-half, -half, half, half)
+    }
+
+    pub fn point_rng_upper(&self) -> f32 {
+        ((self.window_edge as f32) / 2.0) - 50.0
+    }
 }
 
-macro_rules! make_bounds {
-    ($lower_x:expr, $lower_y:expr, $upper_x:expr, $upper_y: expr $(,)?) => {
-        Bounds {
-            lower: Point {
-                x: $lower_x,
-                y: $lower_y,
-            },
-            upper: Point {
-                x: $upper_x,
-                y: $upper_y,
-            },
-        }
-    };
+pub type TreeIndex = usize;
+
+pub struct Tree<P = Point> {
+    pub point: P,
+    pub index: usize,
+    pub bounds: Bounds<P>,
+    pub axis: usize,
+    pub left: Option<TreeIndex>,
+    pub right: Option<TreeIndex>,
+    pub removed: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Neighbor {
+    pub index: usize,
+    pub squared_distance: f32,
+}
+
+pub trait Storage<T>: Default + DerefMut<Target = [T]> {
+    fn push(&mut self, value: T);
+    fn truncate(&mut self, len: usize);
+    fn clear(&mut self);
+    fn is_full(&self) -> bool;
+}
+
+impl<T> Storage<T> for Vec<T> {
+    fn push(&mut self, value: T) {
+        Vec::push(self, value);
+    }
+
+    fn truncate(&mut self, len: usize) {
+        Vec::truncate(self, len);
+    }
+
+    fn clear(&mut self) {
+        Vec::clear(self);
+    }
+
+    fn is_full(&self) -> bool {
+        false
+    }
+}
+
+impl<A: Array> Storage<A::Item> for ArrayVec<A> {
+    fn push(&mut self, value: A::Item) {
+        ArrayVec::push(self, value);
+    }
+
+    fn truncate(&mut self, len: usize) {
+        ArrayVec::truncate(self, len);
+    }
+
+    fn clear(&mut self) {
+        ArrayVec::clear(self);
+    }
+
+    fn is_full(&self) -> bool {
+        ArrayVec::is_full(self)
+    }
+}
+
+pub struct KdTree<P = Point, S = Vec<Tree<P>>> {
+    trees: S,
+    root: Option<TreeIndex>,
+    bounds: Bounds<P>,
+    removed: usize,
+    inserted: usize,
+    rebalance_threshold: Option<f32>,
 }
 
-pub struct Tree {
-    pub point: Point,
-    pub bounds: Bounds,
-    pub horizontal: bool,
-    pub left: *mut Tree,
-    pub right: *mut Tree,
+pub type StackKdTree = KdTree<Point, ArrayVec<[Tree; TREES_CAP]>>;
+
+impl<P: Coordinates, S: Default> Default for KdTree<P, S> {
+    fn default() -> Self {
+        Self {
+            trees: S::default(),
+            root: None,
+            bounds: Bounds::default(),
+            removed: 0,
+            inserted: 0,
+            rebalance_threshold: None,
+        }
+    }
 }
 
 macro_rules! get_median {
-    ($points:expr, $n:expr, $horizontal:expr $(,)?) => {{
+    ($trees:expr, $n:expr, $axis:expr $(,)?) => {{
         let median: usize = $n / 2;
-        if $horizontal {
-            pdqselect::select_by($points, median, |a, b| {
-                a.x.partial_cmp(&b.x).unwrap()
-            });
-        } else {
-            pdqselect::select_by($points, median, |a, b| {
-                a.y.partial_cmp(&b.y).unwrap()
-            });
-        }
-        $points[median].clone()
+        pdqselect::select_by($trees, median, |a, b| {
+            a.point.get($axis).total_cmp(&b.point.get($axis))
+        });
+        median
     }};
 }
 
-pub unsafe fn make_tree(
-    trees: &mut ArrayVec<[Tree; CAPACITY]>,
-    init_points: &mut [Point],
-    horizontal: bool,
-    bounds: Bounds,
-) -> *mut Tree {
-    let mut stack: ArrayVec<[(*mut Tree, &mut [Point]); CAPACITY]> =
-        ArrayVec::new();
-    let n: usize = init_points.len();
-    if n != 0 {
-        let point: Point = get_median!(init_points, n, horizontal);
-        trees.push(Tree {
-            point,
-            bounds,
-            horizontal,
-            left: ptr::null_mut(),
-            right: ptr::null_mut(),
-        });
-        stack.push((trees.last_mut().unwrap(), init_points));
-    }
-    while !stack.is_empty() {
-        let (tree, points): (*mut Tree, &mut [Point]) = stack.pop().unwrap();
-        let point: &Point = &(*tree).point;
-        let horizontal: bool = (*tree).horizontal;
-        let bounds: &Bounds = &(*tree).bounds;
-        let lower_x: f32 = bounds.lower.x;
-        let lower_y: f32 = bounds.lower.y;
-        let upper_x: f32 = bounds.upper.x;
-        let upper_y: f32 = bounds.upper.y;
-        let (horizontal, left_bounds, right_bounds): (bool, Bounds, Bounds) = {
-            if horizontal {
-                let x: f32 = point.x;
-                (
-                    false,
-                    make_bounds!(lower_x, lower_y, x, upper_y),
-                    make_bounds!(x, lower_y, upper_x, upper_y),
-                )
-            } else {
-                let y: f32 = point.y;
-                (
-                    true,
-                    make_bounds!(lower_x, lower_y, upper_x, y),
-                    make_bounds!(lower_x, y, upper_x, upper_y),
-                )
+fn depth_max(n: usize) -> usize {
+    DEPTH_FACTOR * ((usize::BITS - n.leading_zeros()) as usize)
+}
+
+fn next_axis<P: Coordinates>(axis: usize) -> usize {
+    (axis + 1) % P::DIMENSION
+}
+
+fn split_bounds<P: Coordinates>(
+    bounds: &Bounds<P>,
+    point: &P,
+    axis: usize,
+) -> (Bounds<P>, Bounds<P>) {
+    /* NOTE: `NaN` sorts past every number, so a `NaN` split leaves both
+     * cells as wide as their parent rather than poisoning their bounds.
+     */
+    let split: f32 = point.get(axis);
+    let mut left_bounds: Bounds<P> = *bounds;
+    let mut right_bounds: Bounds<P> = *bounds;
+    if !split.is_nan() {
+        left_bounds.upper.set(axis, split);
+        right_bounds.lower.set(axis, split);
+    }
+    (left_bounds, right_bounds)
+}
+
+fn extent<P: Coordinates>(
+    trees: &[Tree<P>],
+    axis: usize,
+    f: fn(f32, f32) -> f32,
+) -> Option<f32> {
+    trees
+        .iter()
+        .map(|tree| tree.point.get(axis))
+        .filter(|value| !value.is_nan())
+        .reduce(f)
+}
+
+fn make_tree<P: Coordinates>(
+    trees: &mut [Tree<P>],
+    offset: usize,
+    axis: usize,
+    bounds: Bounds<P>,
+) -> Option<TreeIndex> {
+    let n: usize = trees.len();
+    if n == 0 {
+        return None;
+    }
+    let median: usize = get_median!(trees, n, axis);
+    let (mut left_bounds, mut right_bounds): (Bounds<P>, Bounds<P>) =
+        split_bounds(&bounds, &trees[median].point, axis);
+    let (left_trees, trees): (&mut [Tree<P>], &mut [Tree<P>]) =
+        trees.split_at_mut(median);
+    let (tree, right_trees): (&mut Tree<P>, &mut [Tree<P>]) =
+        trees.split_first_mut().unwrap();
+    /* NOTE: Each child cell is shrunk to the points it holds along the split
+     * axis, which leaves a gap the split point can later move through
+     * without the children having to change.
+     */
+    if let Some(upper) = extent(left_trees, axis, f32::max) {
+        left_bounds.upper.set(axis, upper);
+    }
+    if let Some(lower) = extent(right_trees, axis, f32::min) {
+        right_bounds.lower.set(axis, lower);
+    }
+    let child_axis: usize = next_axis::<P>(axis);
+    tree.bounds = bounds;
+    tree.axis = axis;
+    tree.removed = false;
+    tree.left = make_tree(left_trees, offset, child_axis, left_bounds);
+    tree.right =
+        make_tree(right_trees, offset + median + 1, child_axis, right_bounds);
+    Some(offset + median)
+}
+
+impl<P: Coordinates, S: Storage<Tree<P>>> KdTree<P, S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn build(points: &[P], bounds: Bounds<P>) -> Self {
+        let mut tree: Self = Self::new();
+        tree.rebuild(points, bounds);
+        tree
+    }
+
+    pub fn set_rebalance_threshold(&mut self, threshold: Option<f32>) {
+        self.rebalance_threshold = threshold;
+    }
+
+    pub fn rebuild(&mut self, points: &[P], bounds: Bounds<P>) {
+        /* NOTE: Every cell has to contain the points below it for the bounds
+         * pruning to hold, so points which have wandered outside of `bounds`
+         * stretch the root cell.
+         */
+        let bounds: Bounds<P> = points
+            .iter()
+            .fold(bounds, |bounds, point| bounds.include(point));
+        self.trees.clear();
+        for (index, point) in points.iter().enumerate() {
+            self.trees.push(Tree {
+                point: *point,
+                index,
+                bounds,
+                axis: 0,
+                left: None,
+                right: None,
+                removed: false,
+            });
+        }
+        self.bounds = bounds;
+        self.removed = 0;
+        self.inserted = 0;
+        self.root = make_tree(&mut self.trees, 0, 0, bounds);
+    }
+
+    pub fn rebalance(&mut self) {
+        let mut n: usize = 0;
+        for i in 0..self.trees.len() {
+            if !self.trees[i].removed {
+                self.trees.swap(n, i);
+                n += 1;
             }
-        };
-        let (left_points, right_points): (&mut [Point], &mut [Point]) = {
-            let m: usize = points.len();
-            let median: usize = m / 2;
-            let points: *mut Point = points.as_mut_ptr();
-            let offset: usize = median + 1;
-            (
-                slice::from_raw_parts_mut(points, median),
-                slice::from_raw_parts_mut(points.add(offset), m - offset),
-            )
-        };
-        if !left_points.is_empty() {
-            let left_point: Point =
-                get_median!(left_points, left_points.len(), horizontal);
-            trees.push(Tree {
-                point: left_point,
-                bounds: left_bounds,
-                horizontal,
-                left: ptr::null_mut(),
-                right: ptr::null_mut(),
+        }
+        self.trees.truncate(n);
+        self.removed = 0;
+        self.inserted = 0;
+        self.root = make_tree(&mut self.trees, 0, 0, self.bounds);
+    }
+
+    /* NOTE: The threshold only tunes how often the tree is rebuilt for
+     * balance; tombstones are reclaimed once they outnumber the live points
+     * whether or not one is set, so storage never grows without bound.
+     */
+    #[allow(clippy::cast_precision_loss)]
+    fn maybe_rebalance(&mut self) {
+        if (self.len() < self.removed)
+            || self.rebalance_threshold.is_some_and(|threshold| {
+                (threshold * (self.len() as f32))
+                    < ((self.removed + self.inserted) as f32)
+            })
+        {
+            self.rebalance();
+        }
+    }
+
+    pub fn insert(&mut self, index: usize, point: P) {
+        if self.trees.is_full() {
+            self.rebalance();
+        }
+        let tree_index: TreeIndex = self.trees.len();
+        if !self.bounds.contains(&point) {
+            self.bounds = self.bounds.include(&point);
+            self.trees.push(Tree {
+                point,
+                index,
+                bounds: self.bounds,
+                axis: 0,
+                left: None,
+                right: None,
+                removed: false,
             });
-            let left_tree: *mut Tree = trees.last_mut().unwrap();
-            (*tree).left = left_tree;
-            stack.push((left_tree, left_points));
-        }
-        if !right_points.is_empty() {
-            let right_point: Point =
-                get_median!(right_points, right_points.len(), horizontal,);
-            trees.push(Tree {
-                point: right_point,
-                bounds: right_bounds,
-                horizontal,
-                left: ptr::null_mut(),
-                right: ptr::null_mut(),
+            self.rebalance();
+            return;
+        }
+        let mut parent: TreeIndex = match self.root {
+            Some(root) => root,
+            None => {
+                self.trees.push(Tree {
+                    point,
+                    index,
+                    bounds: self.bounds,
+                    axis: 0,
+                    left: None,
+                    right: None,
+                    removed: false,
+                });
+                self.root = Some(tree_index);
+                self.inserted += 1;
+                return;
+            }
+        };
+        let mut depth: usize = 1;
+        loop {
+            depth += 1;
+            /* NOTE: Cells can be narrower than the space their parent routes
+             * to them, so every cell on the way down is grown to take in the
+             * new point.
+             */
+            self.trees[parent].bounds =
+                self.trees[parent].bounds.include(&point);
+            let tree: &Tree<P> = &self.trees[parent];
+            let axis: usize = tree.axis;
+            let (left_bounds, right_bounds): (Bounds<P>, Bounds<P>) =
+                split_bounds(&tree.bounds, &tree.point, axis);
+            let left: bool = point.get(axis).total_cmp(&tree.point.get(axis))
+                == Ordering::Less;
+            let (child, bounds): (Option<TreeIndex>, Bounds<P>) = if left {
+                (tree.left, left_bounds)
+            } else {
+                (tree.right, right_bounds)
+            };
+            if let Some(child) = child {
+                parent = child;
+                continue;
+            }
+            self.trees.push(Tree {
+                point,
+                index,
+                bounds,
+                axis: next_axis::<P>(axis),
+                left: None,
+                right: None,
+                removed: false,
             });
-            let right_tree: *mut Tree = trees.last_mut().unwrap();
-            (*tree).right = right_tree;
-            stack.push((right_tree, right_points));
+            if left {
+                self.trees[parent].left = Some(tree_index);
+            } else {
+                self.trees[parent].right = Some(tree_index);
+            }
+            break;
+        }
+        self.inserted += 1;
+        if depth_max(self.trees.len()) < depth {
+            self.shorten(&point);
+        }
+        self.maybe_rebalance();
+    }
+
+    /* NOTE: Only a rebalance reshapes the tree, and without a threshold
+     * nothing triggers one, so sorted or clustered inserts would grow a chain
+     * as long as the tree and overflow the recursive searches. Once a path
+     * gets too deep, the lowest subtree on it which is too deep for its size
+     * is rebuilt on its own, as in a scapegoat tree.
+     */
+    fn shorten(&mut self, point: &P) {
+        let mut path: Vec<TreeIndex> = Vec::new();
+        let mut next: Option<TreeIndex> = self.root;
+        while let Some(tree_index) = next {
+            path.push(tree_index);
+            let tree: &Tree<P> = &self.trees[tree_index];
+            next =
+                if point.get(tree.axis).total_cmp(&tree.point.get(tree.axis))
+                    == Ordering::Less
+                {
+                    tree.left
+                } else {
+                    tree.right
+                };
+        }
+        for (i, tree_index) in path.iter().enumerate().rev() {
+            let subtree: Vec<TreeIndex> = self.subtree(*tree_index);
+            if depth_max(subtree.len()) < (path.len() - i) {
+                self.rebuild_subtree(*tree_index, &subtree);
+                return;
+            }
         }
     }
-    if trees.is_empty() {
-        ptr::null_mut()
-    } else {
-        &mut trees[0]
+
+    fn subtree(&self, tree_index: TreeIndex) -> Vec<TreeIndex> {
+        let mut stack: Vec<TreeIndex> = vec![tree_index];
+        let mut subtree: Vec<TreeIndex> = Vec::new();
+        while let Some(tree_index) = stack.pop() {
+            let tree: &Tree<P> = &self.trees[tree_index];
+            stack.extend(tree.left);
+            stack.extend(tree.right);
+            subtree.push(tree_index);
+        }
+        subtree
     }
-}
 
-fn squared_distance(a: &Point, b: &Point) -> f32 {
-    let x: f32 = a.x - b.x;
-    let y: f32 = a.y - b.y;
-    (x * x) + (y * y)
-}
+    /* NOTE: The subtree keeps the slots it had, with its new top in the slot
+     * of the old one so that the link from its parent still holds; slots
+     * left over from removed points stay behind as unlinked tombstones until
+     * the next rebalance.
+     */
+    fn rebuild_subtree(&mut self, top: TreeIndex, subtree: &[TreeIndex]) {
+        let (bounds, axis): (Bounds<P>, usize) =
+            (self.trees[top].bounds, self.trees[top].axis);
+        let mut slots: Vec<TreeIndex> = vec![top];
+        let mut trees: Vec<Tree<P>> = Vec::with_capacity(subtree.len());
+        for tree_index in subtree {
+            let tree: &Tree<P> = &self.trees[*tree_index];
+            if *tree_index != top {
+                slots.push(*tree_index);
+            }
+            if !tree.removed {
+                trees.push(Tree {
+                    point: tree.point,
+                    index: tree.index,
+                    bounds,
+                    axis,
+                    left: None,
+                    right: None,
+                    removed: false,
+                });
+            }
+        }
+        let root: Option<TreeIndex> = make_tree(&mut trees, 0, axis, bounds);
+        let mut map: Vec<TreeIndex> = Vec::with_capacity(trees.len());
+        let mut n: usize = 1;
+        for i in 0..trees.len() {
+            if Some(i) == root {
+                map.push(top);
+            } else {
+                map.push(slots[n]);
+                n += 1;
+            }
+        }
+        for (i, mut tree) in trees.into_iter().enumerate() {
+            tree.left = tree.left.map(|left| map[left]);
+            tree.right = tree.right.map(|right| map[right]);
+            self.trees[map[i]] = tree;
+        }
+        for tree_index in &slots[n..] {
+            let tree: &mut Tree<P> = &mut self.trees[*tree_index];
+            tree.left = None;
+            tree.right = None;
+            tree.removed = true;
+        }
+    }
 
-fn bounds_to_point_squared_distance(bounds: &Bounds, point: &Point) -> f32 {
-    let x: f32 = point.x - bounds.lower.x.max(point.x.min(bounds.upper.x));
-    let y: f32 = point.y - bounds.lower.y.max(point.y.min(bounds.upper.y));
-    (x * x) + (y * y)
-}
+    pub fn remove(&mut self, index: usize, point: &P) -> bool {
+        if let Some(tree_index) = self.find(index, point) {
+            self.trees[tree_index].removed = true;
+            self.removed += 1;
+            self.maybe_rebalance();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn update(&mut self, index: usize, from: &P, to: P) -> bool {
+        if let Some(tree_index) = self.find(index, from) {
+            if self.can_move(tree_index, &to) {
+                self.trees[tree_index].point = to;
+            } else {
+                self.trees[tree_index].removed = true;
+                self.removed += 1;
+                self.insert(index, to);
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /* NOTE: A node can move anywhere inside of its own cell so long as it
+     * still splits its children apart, which only constrains it along its
+     * split axis: there it has to stay between the cells of its children.
+     */
+    fn can_move(&self, tree_index: TreeIndex, to: &P) -> bool {
+        let tree: &Tree<P> = &self.trees[tree_index];
+        let split: f32 = to.get(tree.axis);
+        tree.bounds.contains(to)
+            && tree.left.is_none_or(|left| {
+                self.trees[left].bounds.upper.get(tree.axis) <= split
+            })
+            && tree.right.is_none_or(|right| {
+                split <= self.trees[right].bounds.lower.get(tree.axis)
+            })
+    }
+
+    fn find(&self, index: usize, point: &P) -> Option<TreeIndex> {
+        if let Some(root) = self.root {
+            self.find_trees(root, index, point)
+        } else {
+            None
+        }
+    }
 
-pub unsafe fn search_trees(
-    point: &Point,
-    init_tree: *mut Tree,
-    neighbors: &mut ArrayVec<[*const Point; CAPACITY]>,
-) {
-    let mut stack: ArrayVec<[*mut Tree; CAPACITY]> = ArrayVec::new();
-    stack.push(init_tree);
-    while !stack.is_empty() {
-        let tree: *mut Tree = stack.pop().unwrap();
-        let bounds: &Bounds = &(*tree).bounds;
-        if bounds_to_point_squared_distance(bounds, point)
-            < SEARCH_RADIUS_SQUARED
+    fn find_trees(
+        &self,
+        tree_index: TreeIndex,
+        index: usize,
+        point: &P,
+    ) -> Option<TreeIndex> {
+        let tree: &Tree<P> = &self.trees[tree_index];
+        if (!tree.removed) && (tree.index == index) {
+            return Some(tree_index);
+        }
+        let ordering: Ordering =
+            point.get(tree.axis).total_cmp(&tree.point.get(tree.axis));
+        if let (Some(left), true) = (tree.left, ordering != Ordering::Greater)
         {
-            let neighbor: &Point = &(*tree).point;
-            if (point != neighbor)
-                && (squared_distance(point, neighbor) < SEARCH_RADIUS_SQUARED)
+            if let Some(tree_index) = self.find_trees(left, index, point) {
+                return Some(tree_index);
+            }
+        }
+        if let (Some(right), true) = (tree.right, ordering != Ordering::Less) {
+            if let Some(tree_index) = self.find_trees(right, index, point) {
+                return Some(tree_index);
+            }
+        }
+        None
+    }
+
+    pub fn len(&self) -> usize {
+        self.trees.len() - self.removed
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn trees(&self) -> &[Tree<P>] {
+        &self.trees
+    }
+
+    pub fn search(
+        &self,
+        point: &P,
+        radius_squared: f32,
+        neighbors: &mut impl Storage<usize>,
+    ) {
+        if let Some(root) = self.root {
+            self.search_trees(root, point, radius_squared, neighbors);
+        }
+    }
+
+    pub fn within(
+        &self,
+        bounds: &Bounds<P>,
+        neighbors: &mut impl Storage<usize>,
+    ) {
+        if let Some(root) = self.root {
+            self.within_trees(root, bounds, neighbors);
+        }
+    }
+
+    pub fn nearest(&self, point: &P) -> Option<Neighbor> {
+        let mut neighbors: Vec<Neighbor> = Vec::with_capacity(1);
+        if let Some(root) = self.root {
+            self.nearest_trees(root, point, 1, &mut neighbors);
+        }
+        neighbors.pop()
+    }
+
+    pub fn k_nearest(&self, point: &P, k: usize) -> Vec<Neighbor> {
+        let mut neighbors: Vec<Neighbor> = Vec::with_capacity(k);
+        if let (Some(root), true) = (self.root, 0 < k) {
+            self.nearest_trees(root, point, k, &mut neighbors);
+        }
+        neighbors
+    }
+
+    fn within_trees(
+        &self,
+        index: TreeIndex,
+        bounds: &Bounds<P>,
+        neighbors: &mut impl Storage<usize>,
+    ) {
+        let tree: &Tree<P> = &self.trees[index];
+        if tree.bounds.intersects(bounds) {
+            if (!tree.removed) && bounds.contains(&tree.point) {
+                neighbors.push(tree.index);
+            }
+            if let Some(left) = tree.left {
+                self.within_trees(left, bounds, neighbors);
+            }
+            if let Some(right) = tree.right {
+                self.within_trees(right, bounds, neighbors);
+            }
+        }
+    }
+
+    fn nearest_trees(
+        &self,
+        index: TreeIndex,
+        point: &P,
+        k: usize,
+        neighbors: &mut Vec<Neighbor>,
+    ) {
+        /* NOTE: `neighbors` is kept sorted by distance, so its last entry is
+         * the one to beat once `k` candidates have been found.
+         */
+        let tree: &Tree<P> = &self.trees[index];
+        if (k <= neighbors.len())
+            && (neighbors[k - 1].squared_distance
+                <= bounds_to_point_squared_distance(&tree.bounds, point))
+        {
+            return;
+        }
+        let squared_distance: f32 = squared_distance(point, &tree.point);
+        if (!tree.removed)
+            && (!squared_distance.is_nan())
+            && ((neighbors.len() < k)
+                || (squared_distance < neighbors[k - 1].squared_distance))
+        {
+            let i: usize = neighbors.partition_point(|neighbor| {
+                neighbor.squared_distance <= squared_distance
+            });
+            if k <= neighbors.len() {
+                neighbors.pop();
+            }
+            neighbors.insert(
+                i,
+                Neighbor {
+                    index: tree.index,
+                    squared_distance,
+                },
+            );
+        }
+        let left_first: bool =
+            point.get(tree.axis).total_cmp(&tree.point.get(tree.axis))
+                == Ordering::Less;
+        let (first, second): (Option<TreeIndex>, Option<TreeIndex>) =
+            if left_first {
+                (tree.left, tree.right)
+            } else {
+                (tree.right, tree.left)
+            };
+        if let Some(first) = first {
+            self.nearest_trees(first, point, k, neighbors);
+        }
+        if let Some(second) = second {
+            self.nearest_trees(second, point, k, neighbors);
+        }
+    }
+
+    fn search_trees(
+        &self,
+        index: TreeIndex,
+        point: &P,
+        radius_squared: f32,
+        neighbors: &mut impl Storage<usize>,
+    ) {
+        let tree: &Tree<P> = &self.trees[index];
+        if bounds_to_point_squared_distance(&tree.bounds, point)
+            < radius_squared
+        {
+            let neighbor: &P = &tree.point;
+            if (!tree.removed)
+                && (point != neighbor)
+                && (squared_distance(point, neighbor) < radius_squared)
             {
-                neighbors.push_unchecked(neighbor);
+                neighbors.push(tree.index);
             }
-            let left: *mut Tree = (*tree).left;
-            if !left.is_null() {
-                stack.push(left);
+            if let Some(left) = tree.left {
+                self.search_trees(left, point, radius_squared, neighbors);
             }
-            let right: *mut Tree = (*tree).right;
-            if !right.is_null() {
-                stack.push(right);
+            if let Some(right) = tree.right {
+                self.search_trees(right, point, radius_squared, neighbors);
             }
         }
     }
 }
+
+impl<P: Coordinates, S: Storage<Tree<P>>> NeighborIndex<P> for KdTree<P, S> {
+    fn rebuild(&mut self, points: &[P], bounds: Bounds<P>) {
+        KdTree::rebuild(self, points, bounds);
+    }
+
+    fn insert(&mut self, index: usize, point: P) {
+        KdTree::insert(self, index, point);
+    }
+
+    fn update(&mut self, index: usize, from: &P, to: P) -> bool {
+        KdTree::update(self, index, from, to)
+    }
+
+    fn search(
+        &self,
+        point: &P,
+        radius_squared: f32,
+        neighbors: &mut impl Storage<usize>,
+    ) {
+        KdTree::search(self, point, radius_squared, neighbors);
+    }
+
+    fn nearest(&self, point: &P) -> Option<Neighbor> {
+        KdTree::nearest(self, point)
+    }
+}
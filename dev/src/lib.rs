@@ -0,0 +1,16 @@
+pub mod args;
+pub mod batch;
+pub mod canvas;
+pub mod config;
+pub mod frames;
+pub mod geometry;
+pub mod grid;
+pub mod kdtree_lib;
+pub mod neighbor;
+pub mod params;
+pub mod plot;
+pub mod print;
+pub mod raster;
+pub mod replay;
+pub mod snapshot;
+pub mod svg;
@@ -0,0 +1,172 @@
+use crate::geometry::{
+    for_each_image, squared_distance, Bounds, Coordinates, Point,
+};
+use crate::kdtree_lib::{Neighbor, Storage};
+
+pub trait NeighborIndex<P: Coordinates = Point> {
+    fn rebuild(&mut self, points: &[P], bounds: Bounds<P>);
+
+    fn insert(&mut self, index: usize, point: P);
+
+    fn update(&mut self, index: usize, from: &P, to: P) -> bool;
+
+    fn search(
+        &self,
+        point: &P,
+        radius_squared: f32,
+        neighbors: &mut impl Storage<usize>,
+    );
+
+    fn nearest(&self, point: &P) -> Option<Neighbor>;
+
+    fn search_periodic(
+        &self,
+        point: &P,
+        radius_squared: f32,
+        period: &Bounds<P>,
+        neighbors: &mut impl Storage<usize>,
+    ) {
+        /* NOTE: Past half of the period a point would be in range of more
+         * than one image of `point`, so the radius is capped there to keep
+         * to the minimum image and report every neighbour once.
+         */
+        let half_period: f32 = period.half_period();
+        let radius_squared: f32 =
+            radius_squared.min(half_period * half_period);
+        for_each_image(point, radius_squared.sqrt(), period, |image| {
+            self.search(image, radius_squared, neighbors);
+        });
+    }
+
+    fn nearest_periodic(
+        &self,
+        point: &P,
+        period: &Bounds<P>,
+    ) -> Option<Neighbor> {
+        let mut nearest: Option<Neighbor> = None;
+        for_each_image(point, f32::INFINITY, period, |image| {
+            if let Some(neighbor) = self.nearest(image) {
+                if nearest.is_none_or(|nearest| {
+                    neighbor.squared_distance < nearest.squared_distance
+                }) {
+                    nearest = Some(neighbor);
+                }
+            }
+        });
+        nearest
+    }
+}
+
+/* NOTE: Checks every point on every query, which makes it slow but easy to
+ * trust; it is the reference the other indices are tested against.
+ */
+pub struct BruteForce<P = Point> {
+    points: Vec<Option<P>>,
+}
+
+impl<P> Default for BruteForce<P> {
+    fn default() -> Self {
+        Self { points: Vec::new() }
+    }
+}
+
+impl<P: Coordinates> BruteForce<P> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn build(points: &[P]) -> Self {
+        Self {
+            points: points.iter().map(|point| Some(*point)).collect(),
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) -> bool {
+        match self.points.get_mut(index) {
+            Some(point) => point.take().is_some(),
+            None => false,
+        }
+    }
+
+    pub fn within(
+        &self,
+        bounds: &Bounds<P>,
+        neighbors: &mut impl Storage<usize>,
+    ) {
+        for (index, point) in self.points.iter().enumerate() {
+            if let Some(point) = point {
+                if bounds.contains(point) {
+                    neighbors.push(index);
+                }
+            }
+        }
+    }
+
+    pub fn k_nearest(&self, point: &P, k: usize) -> Vec<Neighbor> {
+        let mut neighbors: Vec<Neighbor> = self
+            .points
+            .iter()
+            .enumerate()
+            .filter_map(|(index, neighbor)| {
+                let squared_distance: f32 =
+                    squared_distance(point, neighbor.as_ref()?);
+                if squared_distance.is_nan() {
+                    None
+                } else {
+                    Some(Neighbor {
+                        index,
+                        squared_distance,
+                    })
+                }
+            })
+            .collect();
+        neighbors
+            .sort_by(|a, b| a.squared_distance.total_cmp(&b.squared_distance));
+        neighbors.truncate(k);
+        neighbors
+    }
+}
+
+impl<P: Coordinates> NeighborIndex<P> for BruteForce<P> {
+    fn rebuild(&mut self, points: &[P], _: Bounds<P>) {
+        *self = Self::build(points);
+    }
+
+    fn insert(&mut self, index: usize, point: P) {
+        if self.points.len() <= index {
+            self.points.resize(index + 1, None);
+        }
+        self.points[index] = Some(point);
+    }
+
+    fn update(&mut self, index: usize, _: &P, to: P) -> bool {
+        match self.points.get_mut(index) {
+            Some(Some(point)) => {
+                *point = to;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn search(
+        &self,
+        point: &P,
+        radius_squared: f32,
+        neighbors: &mut impl Storage<usize>,
+    ) {
+        for (index, neighbor) in self.points.iter().enumerate() {
+            if let Some(neighbor) = neighbor {
+                if (point != neighbor)
+                    && (squared_distance(point, neighbor) < radius_squared)
+                {
+                    neighbors.push(index);
+                }
+            }
+        }
+    }
+
+    fn nearest(&self, point: &P) -> Option<Neighbor> {
+        self.k_nearest(point, 1).pop()
+    }
+}
@@ -1,17 +1,33 @@
-use graphics::math::Matrix2d;
-use graphics::Transformed;
+#![allow(clippy::cast_lossless)]
+
 use opengl_graphics::{GlGraphics, OpenGL};
 use piston::event_loop::{EventSettings, Events};
-use piston::input::{RenderArgs, RenderEvent};
+use piston::input::{Button, Key, PressEvent, RenderArgs, RenderEvent};
 use piston::window::WindowSettings;
 use rand::distributions::Uniform;
-use rand::rngs::ThreadRng;
 use rand::Rng;
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+use ranim_core::args::Args;
+use ranim_core::batch;
+use ranim_core::canvas::{Canvas, GraphicsCanvas, Style};
+use ranim_core::config;
+use ranim_core::config::Config;
+use ranim_core::frames;
+use ranim_core::frames::Frames;
+use ranim_core::geometry::Point;
+use ranim_core::params;
+use ranim_core::raster::{Raster, SAMPLES_MAX};
+use ranim_core::replay::{Input, Session};
+use ranim_core::snapshot;
+use ranim_core::snapshot::Snapshot;
 use sdl2_window::Sdl2Window;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
 
 const WINDOW_EDGE: f64 = 800.0;
-const WINDOW_EDGE_HALF: f64 = WINDOW_EDGE / 2.0;
-const WINDOW_EDGE_HALF_MINUS: f64 = -WINDOW_EDGE_HALF;
 
 const ANTI_ALIAS: u8 = 4;
 
@@ -21,158 +37,346 @@ const TEAL: [f32; 4] = [0.17, 0.82, 0.76, 0.35];
 
 const LINE_WIDTH: f64 = 1.15;
 const RECT_PAD: f64 = 10.0;
-const RECT_PAD_2: f64 = RECT_PAD * 2.0;
-
-const POINT_RNG_UPPER: f64 = WINDOW_EDGE_HALF;
-const POINT_RNG_LOWER: f64 = WINDOW_EDGE_HALF_MINUS;
 
-const SPEED_INIT: f64 = 0.0;
-const SPEED_INCREMENT: f64 = 0.015;
-const TRAIL: f64 = 7.5;
+const SPEED_INIT: f32 = 0.0;
+const SPEED_INCREMENT: f32 = 0.015;
+const TRAIL: f32 = 7.5;
 
 const CAPACITY: usize = 20;
-const CAPACITY_MINUS_1: usize = CAPACITY - 1;
 
 const RELOAD_FRAME_INTERVAL: u16 = 60 * 8;
 
-#[derive(Clone, Copy)]
-struct Point {
-    x: f64,
-    y: f64,
+ranim_core::params! {
+    pub struct Params {
+        #[range(1.0..)]
+        window_edge: f64 = WINDOW_EDGE,
+        #[range(1..=SAMPLES_MAX)]
+        anti_alias: u8 = ANTI_ALIAS,
+        light_gray: [f32; 4] = LIGHT_GRAY,
+        dark_gray: [f32; 4] = DARK_GRAY,
+        teal: [f32; 4] = TEAL,
+        #[range(0.0..)]
+        line_width: f64 = LINE_WIDTH,
+        #[range(0.0..)]
+        rect_pad: f64 = RECT_PAD,
+        #[range(1..)]
+        capacity: usize = CAPACITY,
+        speed_init: f32 = SPEED_INIT,
+        speed_increment: f32 = SPEED_INCREMENT,
+        #[range(0.0..)]
+        trail: f32 = TRAIL,
+        #[range(..u16::MAX)]
+        reload_frame_interval: u16 = RELOAD_FRAME_INTERVAL,
+    }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 struct Orbiter {
     pos: Point,
     speed: Point,
 }
 
-unsafe fn update(orbiters: &mut [Orbiter]) {
-    for i in 0..CAPACITY {
-        for j in i..CAPACITY {
+#[derive(Serialize, Deserialize)]
+struct State {
+    counter: u16,
+    orbiters: Vec<Orbiter>,
+}
+
+unsafe fn update(orbiters: &mut [Orbiter], speed_increment: f32) {
+    let n: usize = orbiters.len();
+    for i in 0..n {
+        for j in i..n {
             let a: *mut Orbiter = &mut orbiters[i] as *mut Orbiter;
             let b: *mut Orbiter = &mut orbiters[j] as *mut Orbiter;
             if (*a).pos.x < (*b).pos.x {
-                (*a).speed.x += SPEED_INCREMENT;
-                (*b).speed.x -= SPEED_INCREMENT;
+                (*a).speed.x += speed_increment;
+                (*b).speed.x -= speed_increment;
             } else if (*b).pos.x < (*a).pos.x {
-                (*a).speed.x -= SPEED_INCREMENT;
-                (*b).speed.x += SPEED_INCREMENT;
+                (*a).speed.x -= speed_increment;
+                (*b).speed.x += speed_increment;
             }
             if (*a).pos.y < (*b).pos.y {
-                (*a).speed.y += SPEED_INCREMENT;
-                (*b).speed.y -= SPEED_INCREMENT;
+                (*a).speed.y += speed_increment;
+                (*b).speed.y -= speed_increment;
             } else if (*b).pos.y < (*a).pos.y {
-                (*a).speed.y -= SPEED_INCREMENT;
-                (*b).speed.y += SPEED_INCREMENT;
+                (*a).speed.y -= speed_increment;
+                (*b).speed.y += speed_increment;
             }
         }
     }
     for o in orbiters {
-        o.pos.x += o.speed.x;
-        o.pos.y += o.speed.y;
+        o.pos = o.pos + o.speed;
     }
 }
 
-fn render(gl: &mut GlGraphics, args: &RenderArgs, orbiters: &[Orbiter]) {
-    gl.draw(args.viewport(), |context, gl| {
-        let [width, height]: [f64; 2] = args.window_size;
-        let transform: Matrix2d =
-            context.transform.trans(width / 2.0, height / 2.0);
-        graphics::clear(DARK_GRAY, gl);
-        {
-            let o: &Orbiter = &orbiters[CAPACITY_MINUS_1];
-            let x: f64 = o.pos.x;
-            let y: f64 = o.pos.y;
-            let x_speed: f64 = x - (o.speed.x * TRAIL);
-            let y_speed: f64 = y - (o.speed.y * TRAIL);
-            let (min_x, width): (f64, f64) = {
-                if x < x_speed {
-                    (x, x_speed - x)
-                } else {
-                    (x_speed, x - x_speed)
-                }
-            };
-            let (min_y, height): (f64, f64) = {
-                if y < y_speed {
-                    (y, y_speed - y)
-                } else {
-                    (y_speed, y - y_speed)
-                }
-            };
-            graphics::rectangle(
-                TEAL,
-                [
-                    min_x - RECT_PAD,
-                    min_y - RECT_PAD,
-                    width + RECT_PAD_2,
-                    height + RECT_PAD_2,
-                ],
-                transform,
-                gl,
-            );
-            graphics::line(
-                LIGHT_GRAY,
-                LINE_WIDTH,
-                [x, y, x_speed, y_speed],
-                transform,
-                gl,
-            );
+impl State {
+    fn new(params: &Params) -> Self {
+        Self {
+            counter: params.reload_frame_interval + 1,
+            orbiters: vec![
+                Orbiter {
+                    pos: Point::new(0.0, 0.0),
+                    speed: Point::new(0.0, 0.0),
+                };
+                params.capacity
+            ],
         }
-        for o in orbiters.iter().take(CAPACITY_MINUS_1) {
-            graphics::line(
-                LIGHT_GRAY,
-                LINE_WIDTH,
-                [
-                    o.pos.x,
-                    o.pos.y,
-                    o.pos.x - (o.speed.x * TRAIL),
-                    o.pos.y - (o.speed.y * TRAIL),
-                ],
-                transform,
-                gl,
-            );
+    }
+
+    fn restore(self) -> Result<Self, String> {
+        if self.orbiters.is_empty() {
+            return Err("no orbiters to restore".to_owned());
         }
-    });
+        Ok(self)
+    }
+
+    fn step(
+        &mut self,
+        rng: &mut impl Rng,
+        uniform: Uniform<f32>,
+        params: &Params,
+    ) {
+        if params.reload_frame_interval < self.counter {
+            for o in &mut self.orbiters {
+                o.pos.x = rng.sample(uniform);
+                o.pos.y = rng.sample(uniform);
+                o.speed.x = params.speed_init;
+                o.speed.y = params.speed_init;
+            }
+            self.counter = 0;
+        } else {
+            unsafe {
+                update(&mut self.orbiters, params.speed_increment);
+            }
+            self.counter += 1;
+        }
+    }
+}
+
+fn render(canvas: &mut impl Canvas, orbiters: &[Orbiter], params: &Params) {
+    let line: Style =
+        Style::stroke(params.light_gray, params.line_width * 2.0);
+    let n: usize = orbiters.len() - 1;
+    canvas.clear(params.dark_gray);
+    {
+        let o: &Orbiter = &orbiters[n];
+        let trail: Point = o.pos - o.speed.scale(params.trail);
+        let x: f64 = o.pos.x as f64;
+        let y: f64 = o.pos.y as f64;
+        let x_speed: f64 = trail.x as f64;
+        let y_speed: f64 = trail.y as f64;
+        let (min_x, width): (f64, f64) = {
+            if x < x_speed {
+                (x, x_speed - x)
+            } else {
+                (x_speed, x - x_speed)
+            }
+        };
+        let (min_y, height): (f64, f64) = {
+            if y < y_speed {
+                (y, y_speed - y)
+            } else {
+                (y_speed, y - y_speed)
+            }
+        };
+        canvas.rectangle(
+            &Style::fill(params.teal),
+            [
+                min_x - params.rect_pad,
+                min_y - params.rect_pad,
+                width + (params.rect_pad * 2.0),
+                height + (params.rect_pad * 2.0),
+            ],
+        );
+        canvas.line(&line, [x, y, x_speed, y_speed]);
+    }
+    for o in orbiters.iter().take(n) {
+        let trail: Point = o.pos - o.speed.scale(params.trail);
+        canvas.line(
+            &line,
+            [
+                o.pos.x as f64,
+                o.pos.y as f64,
+                trail.x as f64,
+                trail.y as f64,
+            ],
+        );
+    }
+}
+
+fn simulate(seed: u64, steps: u64, params: &Params) -> State {
+    let mut rng: Pcg64 = Pcg64::seed_from_u64(seed);
+    let point_rng_upper: f32 = (params.window_edge as f32) / 2.0;
+    let uniform: Uniform<f32> =
+        Uniform::new_inclusive(-point_rng_upper, point_rng_upper);
+    let mut state: State = State::new(params);
+    for _ in 0..steps {
+        state.step(&mut rng, uniform, params);
+    }
+    state
 }
 
 fn main() {
+    let mut params: Params = Params::default();
+    let mut args: Args = Args::from_env(&mut params);
+    if args.svg.is_some() || args.plot.is_some() {
+        eprintln!("orbits has no svg or plot export");
+        process::exit(2)
+    }
+    let restored: Option<Snapshot<Params, State>> = args.restore(&mut params);
+    let mut session: Session<Params> = args.session(&mut params);
+    if let Some(plan) = args.batch(&params, |params| {
+        (params.window_edge as u32, params.window_edge as u32)
+    }) {
+        let colors: [[f32; 4]; 2] = [params.dark_gray, params.light_gray];
+        let result: io::Result<PathBuf> =
+            batch::run(&plan, "orbits", colors, |tile| {
+                let edge: u32 = tile.params.window_edge as u32;
+                let state: State =
+                    simulate(tile.seed, plan.steps, &tile.params);
+                let mut raster: Raster =
+                    Raster::new(edge, edge, tile.params.anti_alias);
+                raster.draw(|context, raster| {
+                    render(
+                        &mut GraphicsCanvas::new(context, raster),
+                        &state.orbiters,
+                        &tile.params,
+                    )
+                });
+                raster
+            });
+        match result {
+            Ok(path) => println!("{}", path.display()),
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(1)
+            }
+        }
+        return;
+    }
+    let seed: u64 = restored
+        .as_ref()
+        .map_or_else(|| args.seed(), |snapshot| snapshot.seed);
+    println!("seed {}", seed);
+    print!("{}", params::describe(&params));
+    let steps: Option<u64> = args.steps;
+    let mut export: Option<Frames> = args.frames.clone().map(|dir| {
+        Frames::new(
+            dir,
+            params.window_edge as u32,
+            params.window_edge as u32,
+            params.anti_alias,
+        )
+        .unwrap()
+    });
+    let point_rng_upper: f32 = (params.window_edge as f32) / 2.0;
+    let uniform: Uniform<f32> =
+        Uniform::new_inclusive(-point_rng_upper, point_rng_upper);
+    let (mut rng, mut state, mut step): (Pcg64, State, u64) = match restored {
+        Some(snapshot) => match snapshot.state.restore() {
+            Ok(state) => (snapshot.rng, state, snapshot.step),
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(2)
+            }
+        },
+        None => (Pcg64::seed_from_u64(seed), State::new(&params), 0),
+    };
+    if let Some(out) = &args.out {
+        while steps.is_some_and(|steps| step < steps) {
+            state.step(&mut rng, uniform, &params);
+            step += 1;
+        }
+        ranim_core::write_image!(
+            out,
+            &args.print(
+                params.window_edge,
+                params.window_edge,
+                params.anti_alias,
+            ),
+            |canvas| render(canvas, &state.orbiters, &params),
+        )
+        .unwrap();
+        println!("{}", out.display());
+        return;
+    }
     let opengl: OpenGL = OpenGL::V3_2;
     let mut window: Sdl2Window =
-        WindowSettings::new("ranim", [WINDOW_EDGE, WINDOW_EDGE])
+        WindowSettings::new("ranim", [params.window_edge, params.window_edge])
             .graphics_api(opengl)
             .exit_on_esc(true)
-            .samples(ANTI_ALIAS)
+            .samples(params.anti_alias)
             .vsync(true)
             .build()
             .unwrap();
     let mut events: Events = Events::new(EventSettings::new());
     let mut gl: GlGraphics = GlGraphics::new(opengl);
-    let mut rng: ThreadRng = rand::thread_rng();
-    let uniform: Uniform<f64> =
-        Uniform::new_inclusive(POINT_RNG_LOWER, POINT_RNG_UPPER);
-    let mut orbiters: [Orbiter; CAPACITY] = [Orbiter {
-        pos: Point { x: 0.0, y: 0.0 },
-        speed: Point { x: 0.0, y: 0.0 },
-    }; CAPACITY];
-    let mut counter: u16 = RELOAD_FRAME_INTERVAL + 1;
     while let Some(event) = events.next(&mut window) {
-        if let Some(args) = event.render_args() {
-            if RELOAD_FRAME_INTERVAL < counter {
-                for o in &mut orbiters {
-                    o.pos.x = rng.sample(uniform);
-                    o.pos.y = rng.sample(uniform);
-                    o.speed.x = SPEED_INIT;
-                    o.speed.y = SPEED_INIT;
+        let live: Vec<Input<Params>> =
+            event.press_args().map(Input::Press).into_iter().collect();
+        let render_args: Option<RenderArgs> = event.render_args();
+        for input in session.inputs(step, live, render_args.is_some()) {
+            match input {
+                Input::Press(Button::Keyboard(Key::K)) => {
+                    let path: PathBuf = frames::snapshot_path(
+                        Path::new("."),
+                        "orbits",
+                        "json",
+                    );
+                    let snapshot: Snapshot<Params, &State> = Snapshot {
+                        seed,
+                        step,
+                        rng: rng.clone(),
+                        params: params.clone(),
+                        state: &state,
+                    };
+                    snapshot::save(&path, &snapshot).unwrap();
+                    println!("{}", path.display());
                 }
-                counter = 0;
-            } else {
-                unsafe {
-                    update(&mut orbiters);
+                Input::Press(Button::Keyboard(Key::C)) => {
+                    let path: PathBuf = frames::snapshot_path(
+                        Path::new("."),
+                        "orbits",
+                        "toml",
+                    );
+                    let config: Config<Params> = Config {
+                        seed: Some(seed),
+                        steps,
+                        params: params.clone(),
+                    };
+                    config::save(&path, &config).unwrap();
+                    println!("{}", path.display());
                 }
-                counter += 1;
+                _ => (),
             }
-            render(&mut gl, &args, &orbiters);
         }
+        if let Some(args) = render_args {
+            state.step(&mut rng, uniform, &params);
+            gl.draw(args.viewport(), |context, gl| {
+                render(
+                    &mut GraphicsCanvas::new(context, gl),
+                    &state.orbiters,
+                    &params,
+                )
+            });
+            if let Some(export) = &mut export {
+                export
+                    .write(|context, raster| {
+                        render(
+                            &mut GraphicsCanvas::new(context, raster),
+                            &state.orbiters,
+                            &params,
+                        )
+                    })
+                    .unwrap();
+            }
+            step += 1;
+            if steps.is_some_and(|steps| steps <= step) {
+                break;
+            }
+        }
+    }
+    if let Some(path) = session.finish(step).unwrap() {
+        println!("{}", path.display());
     }
 }
@@ -0,0 +1,193 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::ops::RangeBounds;
+
+pub trait Params: Clone + Default + Serialize + DeserializeOwned {
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String>;
+
+    fn fields(&self) -> Vec<(&'static str, String)>;
+
+    fn validate(&self) -> Result<(), String>;
+}
+
+pub trait Value: Sized {
+    fn parse(value: &str) -> Option<Self>;
+
+    fn format(&self) -> String;
+
+    fn is_finite(&self) -> bool {
+        true
+    }
+}
+
+macro_rules! impl_value {
+    ($($type:ty),* $(,)?) => {
+        $(impl Value for $type {
+            fn parse(value: &str) -> Option<Self> {
+                value.trim().parse().ok()
+            }
+
+            fn format(&self) -> String {
+                self.to_string()
+            }
+        })*
+    };
+}
+
+impl_value!(bool, u8, u16, u32, u64, usize);
+
+macro_rules! impl_float_value {
+    ($($type:ty),* $(,)?) => {
+        $(impl Value for $type {
+            fn parse(value: &str) -> Option<Self> {
+                value.trim().parse().ok()
+            }
+
+            fn format(&self) -> String {
+                self.to_string()
+            }
+
+            fn is_finite(&self) -> bool {
+                <$type>::is_finite(*self)
+            }
+        })*
+    };
+}
+
+impl_float_value!(f32, f64);
+
+/* NOTE: Colors are written as TOML arrays, but the brackets are optional
+ * so that `--cyan 0.5,1,0.87,1` works on the command line.
+ */
+impl Value for [f32; 4] {
+    fn parse(value: &str) -> Option<Self> {
+        let value: &str = value.trim();
+        let value: &str = value
+            .strip_prefix('[')
+            .and_then(|value| value.strip_suffix(']'))
+            .unwrap_or(value);
+        let mut color: [f32; 4] = [0.0; 4];
+        let mut channels = value.split(',');
+        for channel in &mut color {
+            *channel = channels.next()?.trim().parse().ok()?;
+        }
+        if channels.next().is_some() {
+            return None;
+        }
+        Some(color)
+    }
+
+    fn format(&self) -> String {
+        format!("[{}, {}, {}, {}]", self[0], self[1], self[2], self[3])
+    }
+
+    fn is_finite(&self) -> bool {
+        self.iter().all(|channel| channel.is_finite())
+    }
+}
+
+/* NOTE: Floats parse `NaN` and `inf`, from flags and from TOML alike, so
+ * every field has to be finite on top of sitting inside its range.
+ */
+pub fn check<T: Value + PartialOrd>(
+    name: &str,
+    value: &T,
+    range: impl RangeBounds<T>,
+) -> Result<(), String> {
+    if value.is_finite() && range.contains(value) {
+        Ok(())
+    } else {
+        Err(format!("bad value `{}` for `{}`", value.format(), name))
+    }
+}
+
+/* NOTE: Lines are written as `name = value`, so the output can be pasted
+ * straight back in as a config.
+ */
+pub fn describe(params: &impl Params) -> String {
+    params
+        .fields()
+        .into_iter()
+        .map(|(name, value)| format!("{} = {}\n", name, value))
+        .collect()
+}
+
+/* NOTE: A field can carry `#[range(...)]` for the values it takes without
+ * the sketch panicking; `validate` rejects anything outside of it.
+ */
+#[macro_export]
+macro_rules! params {
+    (@range) => {
+        ..
+    };
+    (@range $range:expr) => {
+        $range
+    };
+    (
+        pub struct $name:ident {
+            $(
+                $(#[range($range:expr)])?
+                $field:ident: $type:ty = $default:expr,
+            )*
+        }
+    ) => {
+        #[derive(
+            Clone,
+            Debug,
+            PartialEq,
+            serde::Serialize,
+            serde::Deserialize,
+        )]
+        #[serde(default, deny_unknown_fields)]
+        pub struct $name {
+            $(pub $field: $type,)*
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self {
+                    $($field: $default,)*
+                }
+            }
+        }
+
+        impl $crate::params::Params for $name {
+            fn set(
+                &mut self,
+                name: &str,
+                value: &str,
+            ) -> Result<(), String> {
+                match name {
+                    $(stringify!($field) => {
+                        self.$field =
+                            <$type as $crate::params::Value>::parse(value)
+                                .ok_or_else(|| {
+                                    format!(
+                                        "bad value `{}` for `{}`",
+                                        value, name,
+                                    )
+                                })?;
+                    })*
+                    _ => return Err(format!("unknown parameter `{}`", name)),
+                }
+                Ok(())
+            }
+
+            fn fields(&self) -> Vec<(&'static str, String)> {
+                vec![$((
+                    stringify!($field),
+                    $crate::params::Value::format(&self.$field),
+                ),)*]
+            }
+
+            fn validate(&self) -> Result<(), String> {
+                $($crate::params::check(
+                    stringify!($field),
+                    &self.$field,
+                    $crate::params!(@range $($range)?),
+                )?;)*
+                Ok(())
+            }
+        }
+    };
+}
@@ -0,0 +1,257 @@
+#![allow(clippy::cast_possible_truncation)]
+
+use crate::frames;
+use crate::geometry::{squared_distance, Bounds, Point};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const HPGL_UNITS_PER_MM: f32 = 40.0;
+
+pub const PEN_UP_Z: f32 = 5.0;
+pub const PEN_DOWN_Z: f32 = 0.0;
+pub const PLUNGE_RATE: f32 = 500.0;
+pub const FEED_RATE: f32 = 3000.0;
+
+pub type Stroke = Vec<Point>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Paper {
+    pub width: f32,
+    pub height: f32,
+    pub margin: f32,
+}
+
+impl Paper {
+    pub const A4: Self = Self {
+        width: 210.0,
+        height: 297.0,
+        margin: 15.0,
+    };
+    pub const A3: Self = Self {
+        width: 297.0,
+        height: 420.0,
+        margin: 15.0,
+    };
+
+    pub fn parse(size: &str, margin: f32) -> Result<Self, String> {
+        match size.to_ascii_lowercase().as_str() {
+            "a4" => Ok(Self { margin, ..Self::A4 }),
+            "a3" => Ok(Self { margin, ..Self::A3 }),
+            size => {
+                let parse = |value: &str| -> Result<f32, String> {
+                    match value.parse::<f32>() {
+                        Ok(value) if 0.0 < value => Ok(value),
+                        _ => Err(format!("bad paper size `{}`", size)),
+                    }
+                };
+                match size.split_once('x') {
+                    Some((width, height)) => Ok(Self {
+                        width: parse(width)?,
+                        height: parse(height)?,
+                        margin,
+                    }),
+                    None => Err(format!("bad paper size `{}`", size)),
+                }
+            }
+        }
+    }
+
+    /* NOTE: Maps `bounds` onto the area inside the margins, keeping its
+     * aspect ratio and centering it. Paper `y` points up, so the drawing is
+     * flipped to come out the same way round as on screen.
+     */
+    pub fn fit(&self, bounds: &Bounds) -> impl Fn(&Point) -> Point {
+        let size: Point = bounds.upper - bounds.lower;
+        let inner: Point = Point::new(
+            (self.width - (self.margin * 2.0)).max(0.0),
+            (self.height - (self.margin * 2.0)).max(0.0),
+        );
+        let scale: f32 = (inner.x / size.x).min(inner.y / size.y);
+        let offset: Point = Point::new(
+            self.margin + ((inner.x - (size.x * scale)) / 2.0),
+            self.margin + ((inner.y - (size.y * scale)) / 2.0),
+        );
+        let (lower_x, upper_y): (f32, f32) = (bounds.lower.x, bounds.upper.y);
+        move |point: &Point| {
+            Point::new(
+                offset.x + ((point.x - lower_x) * scale),
+                offset.y + ((upper_y - point.y) * scale),
+            )
+        }
+    }
+}
+
+impl Default for Paper {
+    fn default() -> Self {
+        Self::A4
+    }
+}
+
+fn key(point: &Point) -> (u32, u32) {
+    (point.x.to_bits(), point.y.to_bits())
+}
+
+pub fn merge(segments: &[(Point, Point)]) -> Vec<Stroke> {
+    /* NOTE: Segments become edges of a graph whose vertices are their
+     * exactly equal ends. Walks start from odd vertices first, since a
+     * stroke can only end at one of those, and each walk follows unused
+     * edges for as long as it can.
+     */
+    let mut vertices: Vec<Point> = Vec::new();
+    let mut keys: HashMap<(u32, u32), usize> = HashMap::new();
+    let mut edges: Vec<Vec<(usize, usize)>> = Vec::new();
+    let mut vertex = |point: &Point, edges: &mut Vec<Vec<(usize, usize)>>| {
+        *keys.entry(key(point)).or_insert_with(|| {
+            vertices.push(*point);
+            edges.push(Vec::new());
+            vertices.len() - 1
+        })
+    };
+    for (i, (a, b)) in segments.iter().enumerate() {
+        let a: usize = vertex(a, &mut edges);
+        let b: usize = vertex(b, &mut edges);
+        edges[a].push((i, b));
+        edges[b].push((i, a));
+    }
+    let mut used: Vec<bool> = vec![false; segments.len()];
+    let mut next: Vec<usize> = vec![0; vertices.len()];
+    let mut starts: Vec<usize> = (0..vertices.len()).collect();
+    starts.sort_by_key(|start| edges[*start].len().is_multiple_of(2));
+    let mut strokes: Vec<Stroke> = Vec::new();
+    for start in starts {
+        loop {
+            let mut stroke: Stroke = vec![vertices[start]];
+            let mut current: usize = start;
+            while next[current] < edges[current].len() {
+                let (edge, other): (usize, usize) =
+                    edges[current][next[current]];
+                next[current] += 1;
+                if !used[edge] {
+                    used[edge] = true;
+                    stroke.push(vertices[other]);
+                    current = other;
+                }
+            }
+            if stroke.len() < 2 {
+                break;
+            }
+            strokes.push(stroke);
+        }
+    }
+    strokes
+}
+
+pub fn order(strokes: &mut Vec<Stroke>, start: Point) {
+    let mut remaining: Vec<Stroke> = std::mem::take(strokes);
+    let mut pen: Point = start;
+    while !remaining.is_empty() {
+        let mut best: (usize, bool, f32) = (0, false, f32::INFINITY);
+        for (i, stroke) in remaining.iter().enumerate() {
+            let first: f32 = squared_distance(&pen, &stroke[0]);
+            let last: f32 = squared_distance(&pen, stroke.last().unwrap());
+            if first < best.2 {
+                best = (i, false, first);
+            }
+            if last < best.2 {
+                best = (i, true, last);
+            }
+        }
+        let mut stroke: Stroke = remaining.swap_remove(best.0);
+        if best.1 {
+            stroke.reverse();
+        }
+        pen = *stroke.last().unwrap();
+        strokes.push(stroke);
+    }
+}
+
+pub fn travel(strokes: &[Stroke], start: Point) -> f32 {
+    let mut pen: Point = start;
+    let mut travel: f32 = 0.0;
+    for stroke in strokes {
+        travel += (stroke[0] - pen).length();
+        pen = *stroke.last().unwrap();
+    }
+    travel
+}
+
+pub fn strokes(
+    segments: &[(Point, Point)],
+    bounds: &Bounds,
+    paper: &Paper,
+) -> Vec<Stroke> {
+    let clipped: Vec<(Point, Point)> = segments
+        .iter()
+        .filter_map(|(a, b)| bounds.clip(a, b))
+        .filter(|(a, b)| {
+            (a != b)
+                && a.x.is_finite()
+                && a.y.is_finite()
+                && b.x.is_finite()
+                && b.y.is_finite()
+        })
+        .collect();
+    let fit = paper.fit(bounds);
+    let mut strokes: Vec<Stroke> = merge(&clipped)
+        .into_iter()
+        .map(|stroke| stroke.iter().map(&fit).collect())
+        .collect();
+    order(&mut strokes, Point::new(0.0, 0.0));
+    strokes
+}
+
+pub fn hpgl(strokes: &[Stroke]) -> String {
+    macro_rules! units {
+        ($point:expr $(,)?) => {
+            format!(
+                "{},{}",
+                ($point.x * HPGL_UNITS_PER_MM).round() as i32,
+                ($point.y * HPGL_UNITS_PER_MM).round() as i32,
+            )
+        };
+    }
+    let mut hpgl: String = String::from("IN;SP1;\n");
+    for stroke in strokes {
+        let points: Vec<String> =
+            stroke[1..].iter().map(|point| units!(point)).collect();
+        writeln!(hpgl, "PU{};PD{};", units!(stroke[0]), points.join(","))
+            .unwrap();
+    }
+    hpgl.push_str("PU0,0;SP0;\n");
+    hpgl
+}
+
+pub fn gcode(strokes: &[Stroke]) -> String {
+    let mut gcode: String = format!("G21\nG90\nG0 Z{:.3}\n", PEN_UP_Z);
+    for stroke in strokes {
+        writeln!(gcode, "G0 X{:.3} Y{:.3}", stroke[0].x, stroke[0].y).unwrap();
+        writeln!(gcode, "G1 Z{:.3} F{:.0}", PEN_DOWN_Z, PLUNGE_RATE).unwrap();
+        for point in &stroke[1..] {
+            writeln!(
+                gcode,
+                "G1 X{:.3} Y{:.3} F{:.0}",
+                point.x, point.y, FEED_RATE,
+            )
+            .unwrap();
+        }
+        writeln!(gcode, "G0 Z{:.3}", PEN_UP_Z).unwrap();
+    }
+    gcode.push_str("G0 X0 Y0\nM2\n");
+    gcode
+}
+
+pub fn write(
+    dir: &Path,
+    name: &str,
+    strokes: &[Stroke],
+) -> io::Result<(PathBuf, PathBuf)> {
+    fs::create_dir_all(dir)?;
+    let hpgl_path: PathBuf = frames::snapshot_path(dir, name, "hpgl");
+    let gcode_path: PathBuf = hpgl_path.with_extension("gcode");
+    fs::write(&hpgl_path, hpgl(strokes))?;
+    fs::write(&gcode_path, gcode(strokes))?;
+    Ok((hpgl_path, gcode_path))
+}
@@ -0,0 +1,154 @@
+use crate::canvas::GraphicsCanvas;
+use crate::frames;
+use crate::raster::Raster;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const CHANNELS: usize = 4;
+
+const BAND_PIXELS: usize = 1 << 24;
+
+const MM_PER_INCH: f64 = 25.4;
+
+pub const EDGE_MAX: u32 = 1 << 16;
+
+/* NOTE: `view` is the sketch's own coordinate space and `size` the output
+ * in pixels; everything drawn, strokes and radii included, is scaled by the
+ * same factor between the two.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Print {
+    pub view: [f64; 2],
+    pub size: [u32; 2],
+    pub samples: u8,
+    pub supersample: u32,
+    pub dpi: Option<f64>,
+}
+
+impl Print {
+    pub fn new(width: f64, height: f64, samples: u8) -> Self {
+        Self {
+            view: [width, height],
+            size: [width.round() as u32, height.round() as u32],
+            samples,
+            supersample: 1,
+            dpi: None,
+        }
+    }
+
+    pub fn with_edge(self, edge: u32) -> Self {
+        let scale: f64 = f64::from(edge) / self.view[0].max(self.view[1]);
+        Self {
+            size: [
+                (self.view[0] * scale).round() as u32,
+                (self.view[1] * scale).round() as u32,
+            ],
+            ..self
+        }
+    }
+
+    /* NOTE: `new` and `with_edge` saturate out of range sizes rather than
+     * fail, so anything built from user input goes through here.
+     */
+    pub fn check(&self) -> Result<(), String> {
+        let [width, height]: [u32; 2] = self.size;
+        if (width == 0)
+            || (height == 0)
+            || (EDGE_MAX < width.max(height))
+            || (self.supersample == 0)
+            || width.checked_mul(self.supersample).is_none()
+            || !self.scale().is_finite()
+        {
+            return Err(format!(
+                "a {}x{} print at {}x supersampling is out of range",
+                width, height, self.supersample,
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn scale(&self) -> f64 {
+        (f64::from(self.size[0]) / self.view[0])
+            .min(f64::from(self.size[1]) / self.view[1])
+    }
+
+    pub fn svg_size(&self) -> [String; 2] {
+        let size = |pixels: u32| match self.dpi {
+            Some(dpi) => {
+                format!("{:.1}mm", (f64::from(pixels) / dpi) * MM_PER_INCH)
+            }
+            None => pixels.to_string(),
+        };
+        [size(self.size[0]), size(self.size[1])]
+    }
+}
+
+fn downsample(raster: &Raster, factor: u32, pixels: &mut Vec<u8>) {
+    let stride: usize = raster.width() as usize * CHANNELS;
+    let factor: usize = factor as usize;
+    let n: u32 = (factor * factor) as u32;
+    let source: &[u8] = raster.pixels();
+    for y in 0..(raster.height() as usize / factor) {
+        for x in 0..(raster.width() as usize / factor) {
+            let mut sum: [u32; CHANNELS] = [0; CHANNELS];
+            for dy in 0..factor {
+                let row: usize = ((y * factor) + dy) * stride;
+                for dx in 0..factor {
+                    let i: usize = row + (((x * factor) + dx) * CHANNELS);
+                    for (channel, value) in sum.iter_mut().enumerate() {
+                        *value += u32::from(source[i + channel]);
+                    }
+                }
+            }
+            pixels
+                .extend(sum.iter().map(|value| ((value + (n / 2)) / n) as u8));
+        }
+    }
+}
+
+/* NOTE: The output is drawn in horizontal bands, each at `supersample`
+ * times the final resolution and then box filtered down, so a large print
+ * never needs the whole supersampled image in memory at once. The final
+ * pixels are still held in full, as the PNG encoder wants them in one go.
+ */
+pub fn write_png<F>(path: &Path, print: &Print, mut draw: F) -> io::Result<()>
+where
+    F: FnMut(&mut GraphicsCanvas<Raster>),
+{
+    let too_large =
+        || io::Error::new(io::ErrorKind::InvalidInput, "print is too large");
+    let [width, height]: [u32; 2] = print.size;
+    let supersample: u32 = print.supersample;
+    let scale: f64 = print.scale() * f64::from(supersample);
+    let band_width: u32 =
+        width.checked_mul(supersample).ok_or_else(too_large)?;
+    let band_rows: u32 = (((BAND_PIXELS / band_width.max(1) as usize) as u32)
+        / supersample)
+        .max(1);
+    let capacity: usize = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|n| n.checked_mul(CHANNELS))
+        .ok_or_else(too_large)?;
+    let mut pixels: Vec<u8> = Vec::with_capacity(capacity);
+    let mut y: u32 = 0;
+    while y < height {
+        let rows: u32 = band_rows.min(height - y);
+        let mut raster: Raster =
+            Raster::new(band_width, rows * supersample, print.samples);
+        let center: [f64; 2] = [
+            f64::from(band_width) / 2.0,
+            ((f64::from(height) / 2.0) - f64::from(y))
+                * f64::from(supersample),
+        ];
+        raster.draw(|context, raster| {
+            draw(&mut GraphicsCanvas::view(context, raster, center, scale))
+        });
+        downsample(&raster, supersample, &mut pixels);
+        y += rows;
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    frames::write_png_dpi(path, width, height, &pixels, print.dpi)
+}
@@ -0,0 +1,384 @@
+#![allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+
+use graphics::draw_state::{Blend, Stencil};
+use graphics::{Context, DrawState, Graphics, ImageSize};
+
+pub const SAMPLES_MAX: u8 = 4;
+
+const CHANNELS: usize = 4;
+
+pub struct Texture {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Texture {
+    pub fn from_rgba(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        assert_eq!(
+            pixels.len(),
+            (width as usize) * (height as usize) * CHANNELS
+        );
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    fn sample(&self, uv: [f32; 2]) -> [f32; 4] {
+        let x: u32 =
+            ((uv[0] * (self.width as f32)) as u32).min(self.width - 1);
+        let y: u32 =
+            ((uv[1] * (self.height as f32)) as u32).min(self.height - 1);
+        let i: usize =
+            (((y as usize) * (self.width as usize)) + (x as usize)) * CHANNELS;
+        let mut color: [f32; 4] = [0.0; 4];
+        for (channel, value) in color.iter_mut().enumerate() {
+            *value = f32::from(self.pixels[i + channel]) / 255.0;
+        }
+        color
+    }
+}
+
+impl ImageSize for Texture {
+    fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+}
+
+struct Triangle {
+    vertices: [[f32; 2]; 3],
+    area: f32,
+}
+
+fn edge(a: [f32; 2], b: [f32; 2], point: [f32; 2]) -> f32 {
+    ((b[0] - a[0]) * (point[1] - a[1])) - ((b[1] - a[1]) * (point[0] - a[0]))
+}
+
+impl Triangle {
+    fn weights(&self, point: [f32; 2]) -> [f32; 3] {
+        let [a, b, c]: [[f32; 2]; 3] = self.vertices;
+        [
+            edge(b, c, point) / self.area,
+            edge(c, a, point) / self.area,
+            edge(a, b, point) / self.area,
+        ]
+    }
+
+    fn contains(&self, point: [f32; 2]) -> bool {
+        self.weights(point).iter().all(|weight| 0.0 <= *weight)
+    }
+}
+
+pub struct Raster {
+    width: u32,
+    height: u32,
+    samples: u8,
+    pixels: Vec<u8>,
+    stencil: Vec<u8>,
+    masks: Vec<u16>,
+    touched: Vec<usize>,
+}
+
+impl Raster {
+    pub fn new(width: u32, height: u32, samples: u8) -> Self {
+        assert!((0 < samples) && (samples <= SAMPLES_MAX));
+        let n: usize = (width as usize)
+            .checked_mul(height as usize)
+            .expect("raster is too large");
+        Self {
+            width,
+            height,
+            samples,
+            pixels: vec![0; n * CHANNELS],
+            stencil: vec![0; n],
+            masks: vec![0; n],
+            touched: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn into_pixels(self) -> Vec<u8> {
+        self.pixels
+    }
+
+    pub fn context(&self) -> Context {
+        Context::new_abs(f64::from(self.width), f64::from(self.height))
+    }
+
+    pub fn draw<F, U>(&mut self, f: F) -> U
+    where
+        F: FnOnce(Context, &mut Self) -> U,
+    {
+        let context: Context = self.context();
+        f(context, self)
+    }
+
+    fn clip(&self, draw_state: &DrawState) -> [u32; 4] {
+        match draw_state.scissor {
+            Some([x, y, width, height]) => [
+                x.min(self.width),
+                y.min(self.height),
+                (x + width).min(self.width),
+                (y + height).min(self.height),
+            ],
+            None => [0, 0, self.width, self.height],
+        }
+    }
+
+    fn to_pixels(&self, vertices: &[[f32; 2]]) -> Option<Triangle> {
+        /* NOTE: Vertices arrive in normalized device coordinates, with `y`
+         * pointing up.
+         */
+        let width: f32 = self.width as f32;
+        let height: f32 = self.height as f32;
+        let mut pixels: [[f32; 2]; 3] = [[0.0; 2]; 3];
+        for (pixel, vertex) in pixels.iter_mut().zip(vertices) {
+            *pixel = [
+                (vertex[0] + 1.0) * 0.5 * width,
+                (1.0 - vertex[1]) * 0.5 * height,
+            ];
+        }
+        let area: f32 = edge(pixels[0], pixels[1], pixels[2]);
+        if (area == 0.0) || area.is_nan() {
+            None
+        } else {
+            Some(Triangle {
+                vertices: pixels,
+                area,
+            })
+        }
+    }
+
+    fn pixel_range(&self, triangle: &Triangle, clip: [u32; 4]) -> [u32; 4] {
+        let mut lower: [f32; 2] = [f32::INFINITY; 2];
+        let mut upper: [f32; 2] = [f32::NEG_INFINITY; 2];
+        for vertex in &triangle.vertices {
+            for axis in 0..2 {
+                lower[axis] = lower[axis].min(vertex[axis]);
+                upper[axis] = upper[axis].max(vertex[axis]);
+            }
+        }
+        [
+            (lower[0].max(0.0) as u32).max(clip[0]),
+            (lower[1].max(0.0) as u32).max(clip[1]),
+            ((upper[0].max(0.0).ceil()) as u32).min(clip[2]),
+            ((upper[1].max(0.0).ceil()) as u32).min(clip[3]),
+        ]
+    }
+
+    fn cover(&mut self, triangle: &Triangle, clip: [u32; 4]) {
+        let samples: u32 = u32::from(self.samples);
+        let step: f32 = 1.0 / (samples as f32);
+        let [x_lower, y_lower, x_upper, y_upper]: [u32; 4] =
+            self.pixel_range(triangle, clip);
+        for y in y_lower..y_upper {
+            for x in x_lower..x_upper {
+                let mut mask: u16 = 0;
+                for j in 0..samples {
+                    for i in 0..samples {
+                        let point: [f32; 2] = [
+                            (x as f32) + (((i as f32) + 0.5) * step),
+                            (y as f32) + (((j as f32) + 0.5) * step),
+                        ];
+                        if triangle.contains(point) {
+                            mask |= 1 << ((j * samples) + i);
+                        }
+                    }
+                }
+                if mask != 0 {
+                    let index: usize =
+                        ((y as usize) * (self.width as usize)) + (x as usize);
+                    if self.masks[index] == 0 {
+                        self.touched.push(index);
+                    }
+                    self.masks[index] |= mask;
+                }
+            }
+        }
+    }
+
+    fn coverage(&self, mask: u16) -> f32 {
+        let samples: u32 = u32::from(self.samples);
+        (mask.count_ones() as f32) / ((samples * samples) as f32)
+    }
+
+    fn stencil_pass(
+        &mut self,
+        index: usize,
+        stencil: Option<Stencil>,
+    ) -> bool {
+        match stencil {
+            None => true,
+            Some(Stencil::Clip(value)) => {
+                self.stencil[index] = value;
+                false
+            }
+            Some(Stencil::Inside(value)) => self.stencil[index] == value,
+            Some(Stencil::Outside(value)) => self.stencil[index] != value,
+        }
+    }
+
+    fn blend(
+        &mut self,
+        index: usize,
+        color: [f32; 4],
+        coverage: f32,
+        blend: Option<Blend>,
+    ) {
+        let offset: usize = index * CHANNELS;
+        let pixel: &mut [u8] = &mut self.pixels[offset..(offset + CHANNELS)];
+        let mut dst: [f32; 4] = [0.0; 4];
+        for (value, channel) in dst.iter_mut().zip(pixel.iter()) {
+            *value = f32::from(*channel) / 255.0;
+        }
+        let alpha: f32 = color[3];
+        let mut out: [f32; 4] = [0.0; 4];
+        for channel in 0..CHANNELS {
+            let (src, dst_value): (f32, f32) = (color[channel], dst[channel]);
+            let is_alpha: bool = channel == 3;
+            out[channel] = match (blend, is_alpha) {
+                (None, _) => src,
+                (Some(Blend::Alpha), false) => {
+                    (src * alpha) + (dst_value * (1.0 - alpha))
+                }
+                (Some(Blend::Alpha), true) => {
+                    alpha + (dst_value * (1.0 - alpha))
+                }
+                (Some(Blend::Add), _) => src + dst_value,
+                (Some(Blend::Lighter), false) => (src * alpha) + dst_value,
+                (Some(Blend::Multiply), _) => src * dst_value,
+                (Some(Blend::Invert), false) => {
+                    (src * (1.0 - dst_value)) + (dst_value * (1.0 - src))
+                }
+                (Some(Blend::Lighter), true) | (Some(Blend::Invert), true) => {
+                    dst_value
+                }
+            };
+        }
+        for (channel, value) in pixel.iter_mut().enumerate() {
+            let blended: f32 =
+                dst[channel] + ((out[channel] - dst[channel]) * coverage);
+            *value = (blended.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+    }
+
+    fn flush(
+        &mut self,
+        draw_state: &DrawState,
+        color: impl Fn(u32, u32) -> [f32; 4],
+    ) {
+        let touched: Vec<usize> = std::mem::take(&mut self.touched);
+        for index in &touched {
+            let index: usize = *index;
+            let coverage: f32 = self.coverage(self.masks[index]);
+            self.masks[index] = 0;
+            if self.stencil_pass(index, draw_state.stencil) {
+                let x: u32 = (index as u32) % self.width;
+                let y: u32 = (index as u32) / self.width;
+                self.blend(index, color(x, y), coverage, draw_state.blend);
+            }
+        }
+        self.touched = touched;
+        self.touched.clear();
+    }
+}
+
+impl Graphics for Raster {
+    type Texture = Texture;
+
+    fn clear_color(&mut self, color: [f32; 4]) {
+        for pixel in self.pixels.chunks_mut(CHANNELS) {
+            for (value, channel) in pixel.iter_mut().zip(color.iter()) {
+                *value = (channel.clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+        }
+    }
+
+    fn clear_stencil(&mut self, value: u8) {
+        for stencil in &mut self.stencil {
+            *stencil = value;
+        }
+    }
+
+    fn tri_list<F>(
+        &mut self,
+        draw_state: &DrawState,
+        color: &[f32; 4],
+        mut f: F,
+    ) where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]])),
+    {
+        /* NOTE: Coverage is gathered for the whole call before anything is
+         * blended, so the triangles that make up one shape never blend over
+         * each other along their shared edges.
+         */
+        let clip: [u32; 4] = self.clip(draw_state);
+        f(&mut |vertices: &[[f32; 2]]| {
+            for vertices in vertices.chunks_exact(3) {
+                if let Some(triangle) = self.to_pixels(vertices) {
+                    self.cover(&triangle, clip);
+                }
+            }
+        });
+        self.flush(draw_state, |_, _| *color);
+    }
+
+    fn tri_list_uv<F>(
+        &mut self,
+        draw_state: &DrawState,
+        color: &[f32; 4],
+        texture: &Texture,
+        mut f: F,
+    ) where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]])),
+    {
+        let clip: [u32; 4] = self.clip(draw_state);
+        f(&mut |vertices: &[[f32; 2]], uvs: &[[f32; 2]]| {
+            for (vertices, uvs) in
+                vertices.chunks_exact(3).zip(uvs.chunks_exact(3))
+            {
+                if let Some(triangle) = self.to_pixels(vertices) {
+                    self.cover(&triangle, clip);
+                    self.flush(draw_state, |x, y| {
+                        let [a, b, c]: [f32; 3] = triangle
+                            .weights([(x as f32) + 0.5, (y as f32) + 0.5]);
+                        let uv: [f32; 2] = [
+                            ((a * uvs[0][0])
+                                + (b * uvs[1][0])
+                                + (c * uvs[2][0]))
+                                .clamp(0.0, 1.0),
+                            ((a * uvs[0][1])
+                                + (b * uvs[1][1])
+                                + (c * uvs[2][1]))
+                                .clamp(0.0, 1.0),
+                        ];
+                        let mut tinted: [f32; 4] = *color;
+                        for (value, texel) in
+                            tinted.iter_mut().zip(&texture.sample(uv))
+                        {
+                            *value *= texel;
+                        }
+                        tinted
+                    });
+                }
+            }
+        });
+    }
+}
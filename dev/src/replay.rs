@@ -0,0 +1,129 @@
+use crate::config;
+use crate::params::Params;
+use piston::input::Button;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Input<P> {
+    Press(Button),
+    Scroll(f64),
+    Reload(P),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Frame<P> {
+    pub step: u64,
+    pub inputs: Vec<Input<P>>,
+}
+
+/* NOTE: Only frames that saw input are logged; everything else follows
+ * from `seed` and `params`.
+ */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Log<P> {
+    pub seed: u64,
+    pub steps: Option<u64>,
+    pub params: P,
+    pub frames: Vec<Frame<P>>,
+}
+
+impl<P> Log<P> {
+    pub fn new(seed: u64, params: P) -> Self {
+        Self {
+            seed,
+            steps: None,
+            params,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, step: u64, input: Input<P>) {
+        match self.frames.last_mut() {
+            Some(frame) if frame.step == step => frame.inputs.push(input),
+            _ => self.frames.push(Frame {
+                step,
+                inputs: vec![input],
+            }),
+        }
+    }
+}
+
+/* NOTE: A log can be edited by hand, so its params go through the same
+ * checks as flags before anything is replayed.
+ */
+pub fn load<P: Params>(path: &Path) -> Result<Log<P>, String> {
+    let log: Log<P> = config::read(path)?;
+    log.params.validate()?;
+    for frame in &log.frames {
+        for input in &frame.inputs {
+            if let Input::Reload(params) = input {
+                params.validate()?;
+            }
+        }
+    }
+    Ok(log)
+}
+
+pub fn save<P: Serialize>(path: &Path, log: &Log<P>) -> io::Result<()> {
+    config::write(path, log)
+}
+
+pub enum Session<P> {
+    Live,
+    Record { path: PathBuf, log: Log<P> },
+    Replay { log: Log<P>, cursor: usize },
+}
+
+impl<P: Clone + Serialize> Session<P> {
+    pub fn is_replay(&self) -> bool {
+        matches!(self, Self::Replay { .. })
+    }
+
+    /* NOTE: Live input is handled as it arrives, which is always before the
+     * render of `step`; a replay holds its inputs back until that render so
+     * they land on the same frame.
+     */
+    pub fn inputs(
+        &mut self,
+        step: u64,
+        live: Vec<Input<P>>,
+        render: bool,
+    ) -> Vec<Input<P>> {
+        match self {
+            Self::Live => live,
+            Self::Record { log, .. } => {
+                for input in &live {
+                    log.push(step, input.clone());
+                }
+                live
+            }
+            Self::Replay { log, cursor } => {
+                if !render {
+                    return Vec::new();
+                }
+                match log.frames.get(*cursor) {
+                    Some(frame) if frame.step == step => {
+                        *cursor += 1;
+                        frame.inputs.clone()
+                    }
+                    _ => Vec::new(),
+                }
+            }
+        }
+    }
+
+    pub fn finish(&mut self, step: u64) -> io::Result<Option<&Path>> {
+        match self {
+            Self::Record { path, log } => {
+                log.steps = Some(step);
+                save(path, log)?;
+                Ok(Some(path))
+            }
+            _ => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use crate::config;
+use rand_pcg::Pcg64;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+/* NOTE: `rng` is the generator mid-run, so a restored sketch carries on
+ * exactly where the snapshot left off; `seed` is only kept as a label.
+ */
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Snapshot<P, S> {
+    pub seed: u64,
+    pub step: u64,
+    pub rng: Pcg64,
+    pub params: P,
+    pub state: S,
+}
+
+pub fn load<P, S>(path: &Path) -> Result<Snapshot<P, S>, String>
+where
+    P: DeserializeOwned,
+    S: DeserializeOwned,
+{
+    config::read(path)
+}
+
+pub fn save<P, S>(path: &Path, snapshot: &Snapshot<P, S>) -> io::Result<()>
+where
+    P: Serialize,
+    S: Serialize,
+{
+    config::write(path, snapshot)
+}
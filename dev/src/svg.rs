@@ -0,0 +1,156 @@
+use crate::canvas::{Canvas, Style};
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+fn number(value: f64) -> String {
+    let value: String = format!("{:.3}", value);
+    let value: &str = value.trim_end_matches('0').trim_end_matches('.');
+    if value == "-0" {
+        "0".into()
+    } else {
+        value.into()
+    }
+}
+
+fn paint(attribute: &str, color: [f32; 4]) -> String {
+    let [r, g, b, a]: [f32; 4] = color;
+    let mut paint: String = format!(
+        "{}=\"rgb({},{},{})\"",
+        attribute,
+        (r.clamp(0.0, 1.0) * 255.0).round(),
+        (g.clamp(0.0, 1.0) * 255.0).round(),
+        (b.clamp(0.0, 1.0) * 255.0).round(),
+    );
+    if a < 1.0 {
+        write!(paint, " {}-opacity=\"{}\"", attribute, number(a as f64))
+            .unwrap();
+    }
+    paint
+}
+
+/* NOTE: The origin sits in the middle of the view box, as `Canvas`
+ * expects.
+ */
+pub struct Svg {
+    width: f64,
+    height: f64,
+    size: [String; 2],
+    body: String,
+}
+
+impl Svg {
+    pub fn new(width: f64, height: f64) -> Self {
+        Self {
+            width,
+            height,
+            size: [number(width), number(height)],
+            body: String::new(),
+        }
+    }
+
+    /* NOTE: Only the size the document asks to be shown at; the view box,
+     * and so every coordinate, stays in sketch units.
+     */
+    pub fn set_size(&mut self, width: String, height: String) {
+        self.size = [width, height];
+    }
+
+    pub fn to_document(&self) -> String {
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" \
+             width=\"{}\" height=\"{}\" viewBox=\"{} {} {} {}\">\n{}</svg>\n",
+            self.size[0],
+            self.size[1],
+            number(-self.width / 2.0),
+            number(-self.height / 2.0),
+            number(self.width),
+            number(self.height),
+            self.body,
+        )
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_document())
+    }
+}
+
+impl Canvas for Svg {
+    fn clear(&mut self, color: [f32; 4]) {
+        self.body.clear();
+        self.rectangle(
+            &Style::fill(color),
+            [
+                -self.width / 2.0,
+                -self.height / 2.0,
+                self.width,
+                self.height,
+            ],
+        );
+    }
+
+    fn rectangle(&mut self, style: &Style, rect: [f64; 4]) {
+        let [x, y, width, height]: [f64; 4] = rect;
+        writeln!(
+            self.body,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" {}/>",
+            number(x),
+            number(y),
+            number(width),
+            number(height),
+            paint("fill", style.color),
+        )
+        .unwrap();
+    }
+
+    fn circle(&mut self, style: &Style, center: [f64; 2], radius: f64) {
+        writeln!(
+            self.body,
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" {}/>",
+            number(center[0]),
+            number(center[1]),
+            number(radius),
+            paint("fill", style.color),
+        )
+        .unwrap();
+    }
+
+    fn line(&mut self, style: &Style, line: [f64; 4]) {
+        let [x1, y1, x2, y2]: [f64; 4] = line;
+        writeln!(
+            self.body,
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" {} \
+             stroke-width=\"{}\" stroke-linecap=\"square\"/>",
+            number(x1),
+            number(y1),
+            number(x2),
+            number(y2),
+            paint("stroke", style.color),
+            number(style.width),
+        )
+        .unwrap();
+    }
+
+    fn polyline(&mut self, style: &Style, points: &[[f64; 2]]) {
+        if points.len() < 2 {
+            return;
+        }
+        let points: Vec<String> = points
+            .iter()
+            .map(|[x, y]| format!("{},{}", number(*x), number(*y)))
+            .collect();
+        writeln!(
+            self.body,
+            "<polyline points=\"{}\" fill=\"none\" {} stroke-width=\"{}\" \
+             stroke-linecap=\"round\" stroke-linejoin=\"round\"/>",
+            points.join(" "),
+            paint("stroke", style.color),
+            number(style.width),
+        )
+        .unwrap();
+    }
+}
@@ -2,20 +2,36 @@
 
 mod webs_lib;
 
-use webs_lib::{Edge, Node, Point};
+use webs_lib::{Edge, Node, Params, State};
 
-use arrayvec::ArrayVec;
-use graphics::math::Matrix2d;
-use graphics::Transformed;
 use opengl_graphics::{GlGraphics, OpenGL};
 use piston::event_loop::{EventSettings, Events};
-use piston::input::{RenderArgs, RenderEvent};
+use piston::input::{Button, Key, PressEvent, RenderArgs, RenderEvent};
 use piston::window::WindowSettings;
 use rand::distributions::Uniform;
-use rand::rngs::ThreadRng;
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+use ranim_core::args::Args;
+use ranim_core::batch;
+use ranim_core::canvas::{Canvas, GraphicsCanvas, Style};
+use ranim_core::config;
+use ranim_core::config::{Config, Watch};
+use ranim_core::frames;
+use ranim_core::frames::Frames;
+use ranim_core::geometry::Point;
+use ranim_core::params;
+use ranim_core::plot;
+use ranim_core::plot::{Paper, Stroke};
+use ranim_core::raster::Raster;
+use ranim_core::replay::{Input, Session};
+use ranim_core::snapshot;
+use ranim_core::snapshot::Snapshot;
+use ranim_core::svg::Svg;
 use sdl2_window::Sdl2Window;
 use std::io;
 use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process;
 use std::time::Instant;
 
 struct Rect {
@@ -50,125 +66,305 @@ macro_rules! make_rect {
     }};
 }
 
-unsafe fn render(gl: &mut GlGraphics, args: &RenderArgs, edges: &[Edge]) {
+unsafe fn render(canvas: &mut impl Canvas, edges: &[Edge], params: &Params) {
     let n: usize = edges.len() - 1;
-    gl.draw(args.viewport(), |context, gl| {
-        let [width, height]: [f64; 2] = args.window_size;
-        let transform: Matrix2d =
-            context.transform.trans(width / 2.0, height / 2.0);
-        graphics::clear(webs_lib::DARK_GRAY, gl);
-        {
-            let edge: &Edge = &edges[n];
-            let a: &Point = &(*edge.a).point;
-            let b: &Point = &(*edge.b).point;
-            let a_x: f64 = a.x as f64;
-            let a_y: f64 = a.y as f64;
-            let b_x: f64 = b.x as f64;
-            let b_y: f64 = b.y as f64;
-            let rect: Rect = make_rect!(a_x, a_y, b_x, b_y);
-            graphics::rectangle(
-                webs_lib::TEAL,
-                [
-                    rect.x - webs_lib::RECT_PAD,
-                    rect.y - webs_lib::RECT_PAD,
-                    rect.width + webs_lib::RECT_PAD_2,
-                    rect.height + webs_lib::RECT_PAD_2,
-                ],
-                transform,
-                gl,
-            );
-            graphics::line(
-                webs_lib::CYAN,
-                webs_lib::LINE_WIDTH,
-                [a_x, a_y, b_x, b_y],
-                transform,
-                gl,
-            );
-            graphics::ellipse(
-                webs_lib::CYAN,
-                [
-                    a_x - webs_lib::RADIUS,
-                    a_y - webs_lib::RADIUS,
-                    webs_lib::RADIUS_2,
-                    webs_lib::RADIUS_2,
-                ],
-                transform,
-                gl,
-            );
-            graphics::ellipse(
-                webs_lib::CYAN,
-                [
-                    b_x - webs_lib::RADIUS,
-                    b_y - webs_lib::RADIUS,
-                    webs_lib::RADIUS_2,
-                    webs_lib::RADIUS_2,
-                ],
-                transform,
-                gl,
-            );
-        }
-        for edge in edges.iter().take(n) {
-            let a: &Point = &(*edge.a).point;
-            let b: &Point = &(*edge.b).point;
-            let a_x: f64 = a.x as f64;
-            let a_y: f64 = a.y as f64;
-            let b_x: f64 = b.x as f64;
-            let b_y: f64 = b.y as f64;
-            graphics::line(
-                webs_lib::LIGHT_GRAY,
-                webs_lib::LINE_WIDTH,
-                [a_x, a_y, b_x, b_y],
-                transform,
-                gl,
-            );
-        }
-    });
+    let line_width: f64 = params.line_width * 2.0;
+    canvas.clear(params.dark_gray);
+    {
+        let edge: &Edge = &edges[n];
+        let a: &Point = &(*edge.a).point;
+        let b: &Point = &(*edge.b).point;
+        let a_x: f64 = a.x as f64;
+        let a_y: f64 = a.y as f64;
+        let b_x: f64 = b.x as f64;
+        let b_y: f64 = b.y as f64;
+        let rect: Rect = make_rect!(a_x, a_y, b_x, b_y);
+        canvas.rectangle(
+            &Style::fill(params.teal),
+            [
+                rect.x - params.rect_pad,
+                rect.y - params.rect_pad,
+                rect.width + (params.rect_pad * 2.0),
+                rect.height + (params.rect_pad * 2.0),
+            ],
+        );
+        canvas.line(
+            &Style::stroke(params.cyan, line_width),
+            [a_x, a_y, b_x, b_y],
+        );
+        let node: Style = Style::fill(params.cyan);
+        canvas.circle(&node, [a_x, a_y], params.radius);
+        canvas.circle(&node, [b_x, b_y], params.radius);
+    }
+    let line: Style = Style::stroke(params.light_gray, line_width);
+    for edge in edges.iter().take(n) {
+        let a: &Point = &(*edge.a).point;
+        let b: &Point = &(*edge.b).point;
+        canvas.line(&line, [a.x as f64, a.y as f64, b.x as f64, b.y as f64]);
+    }
+}
+
+unsafe fn segments(edges: &[Edge]) -> Vec<(Point, Point)> {
+    edges
+        .iter()
+        .map(|edge| ((*edge.a).point, (*edge.b).point))
+        .collect()
+}
+
+/* NOTE: `edges` points into `nodes`, so both are handed back together and
+ * have to be dropped together.
+ */
+unsafe fn simulate(
+    seed: u64,
+    steps: u64,
+    params: &Params,
+) -> (Vec<Node>, Vec<Edge>) {
+    let mut rng: Pcg64 = Pcg64::seed_from_u64(seed);
+    let point_rng_upper: f32 = params.point_rng_upper();
+    let uniform: Uniform<f32> =
+        Uniform::new_inclusive(-point_rng_upper, point_rng_upper);
+    let mut nodes: Vec<Node> = Vec::with_capacity(params.nodes_cap);
+    let mut edges: Vec<Edge> = Vec::with_capacity(params.edges_cap);
+    let mut counter: u16 = 0;
+    webs_lib::init(&mut rng, uniform, &mut nodes, &mut edges);
+    for _ in 0..steps {
+        webs_lib::step(
+            &mut rng,
+            uniform,
+            &mut nodes,
+            &mut edges,
+            &mut counter,
+            params,
+        );
+    }
+    (nodes, edges)
 }
 
 fn main() {
-    let opengl: OpenGL = OpenGL::V3_2;
-    let mut window: Sdl2Window = WindowSettings::new(
-        "ranim",
-        [webs_lib::WINDOW_EDGE, webs_lib::WINDOW_EDGE],
-    )
-    .graphics_api(opengl)
-    .exit_on_esc(true)
-    .samples(webs_lib::ANTI_ALIAS)
-    .vsync(true)
-    .build()
-    .unwrap();
-    let mut events: Events = Events::new(EventSettings::new());
-    let mut gl: GlGraphics = GlGraphics::new(opengl);
-    let mut rng: ThreadRng = rand::thread_rng();
-    let uniform: Uniform<f32> = Uniform::new_inclusive(
-        webs_lib::POINT_RNG_LOWER,
-        webs_lib::POINT_RNG_UPPER,
-    );
-    let mut nodes: ArrayVec<[Node; webs_lib::NODES_CAP]> = ArrayVec::new();
-    let mut edges: ArrayVec<[Edge; webs_lib::EDGES_CAP]> = ArrayVec::new();
+    let mut params: Params = Params::default();
+    let mut args: Args = Args::from_env(&mut params);
+    let restored: Option<Snapshot<Params, State>> = args.restore(&mut params);
+    let mut session: Session<Params> = args.session(&mut params);
+    if let Some(plan) = args.batch(&params, |params| {
+        (params.window_edge as u32, params.window_edge as u32)
+    }) {
+        let colors: [[f32; 4]; 2] = [params.dark_gray, params.light_gray];
+        let result: io::Result<PathBuf> =
+            batch::run(&plan, "webs", colors, |tile| {
+                let edge: u32 = tile.params.window_edge as u32;
+                let (_nodes, edges): (Vec<Node>, Vec<Edge>) =
+                    unsafe { simulate(tile.seed, plan.steps, &tile.params) };
+                let mut raster: Raster =
+                    Raster::new(edge, edge, tile.params.anti_alias);
+                raster.draw(|context, raster| unsafe {
+                    render(
+                        &mut GraphicsCanvas::new(context, raster),
+                        &edges,
+                        &tile.params,
+                    )
+                });
+                raster
+            });
+        match result {
+            Ok(path) => println!("{}", path.display()),
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(1)
+            }
+        }
+        return;
+    }
+    let seed: u64 = restored
+        .as_ref()
+        .map_or_else(|| args.seed(), |snapshot| snapshot.seed);
+    println!("seed {}", seed);
+    print!("{}", params::describe(&params));
+    let steps: Option<u64> = args.steps;
+    let svg_dir: PathBuf =
+        args.svg.clone().unwrap_or_else(|| PathBuf::from("."));
+    let plot_dir: PathBuf =
+        args.plot.clone().unwrap_or_else(|| PathBuf::from("."));
+    let paper: Paper = args.paper;
+    let mut export: Option<Frames> = args.frames.clone().map(|dir| {
+        Frames::new(
+            dir,
+            params.window_edge as u32,
+            params.window_edge as u32,
+            params.anti_alias,
+        )
+        .unwrap()
+    });
+    let mut rng: Pcg64 = Pcg64::seed_from_u64(seed);
+    let point_rng_upper: f32 = params.point_rng_upper();
+    let uniform: Uniform<f32> =
+        Uniform::new_inclusive(-point_rng_upper, point_rng_upper);
+    let mut nodes: Vec<Node> = Vec::with_capacity(params.nodes_cap);
+    let mut edges: Vec<Edge> = Vec::with_capacity(params.edges_cap);
     let mut counter: u16 = 0;
+    let mut watch: Option<Watch> = args
+        .config
+        .clone()
+        .filter(|_| !session.is_replay())
+        .map(Watch::new);
+    let mut step: u64 = 0;
     let mut frames: u16 = 0;
     let mut elapsed: f32 = 0.0;
     let mut clock: Instant = Instant::now();
     unsafe {
-        webs_lib::init(&mut rng, uniform, &mut nodes, &mut edges);
-        while let Some(event) = events.next(&mut window) {
-            if let Some(args) = event.render_args() {
-                if (webs_lib::NODES_LIMIT < nodes.len())
-                    || (webs_lib::EDGES_LIMIT < edges.len())
+        match restored {
+            Some(snapshot) => {
+                if let Err(error) =
+                    snapshot.state.restore(&mut nodes, &mut edges)
                 {
-                    nodes.clear();
-                    edges.clear();
-                    webs_lib::init(&mut rng, uniform, &mut nodes, &mut edges);
-                } else if webs_lib::INSERT_FRAME_INTERVAL < counter {
-                    webs_lib::insert(
-                        &mut rng, uniform, &mut nodes, &mut edges,
-                    );
-                    counter = 0;
+                    eprintln!("{}", error);
+                    process::exit(2)
+                }
+                rng = snapshot.rng;
+                step = snapshot.step;
+                counter = snapshot.state.counter;
+            }
+            None => webs_lib::init(&mut rng, uniform, &mut nodes, &mut edges),
+        }
+        if let Some(out) = &args.out {
+            while steps.is_some_and(|steps| step < steps) {
+                webs_lib::step(
+                    &mut rng,
+                    uniform,
+                    &mut nodes,
+                    &mut edges,
+                    &mut counter,
+                    &params,
+                );
+                step += 1;
+            }
+            ranim_core::write_image!(
+                out,
+                &args.print(
+                    params.window_edge,
+                    params.window_edge,
+                    params.anti_alias,
+                ),
+                |canvas| render(canvas, &edges, &params),
+            )
+            .unwrap();
+            println!("{}", out.display());
+            return;
+        }
+        let opengl: OpenGL = OpenGL::V3_2;
+        let mut window: Sdl2Window = WindowSettings::new(
+            "ranim",
+            [params.window_edge, params.window_edge],
+        )
+        .graphics_api(opengl)
+        .exit_on_esc(true)
+        .samples(params.anti_alias)
+        .vsync(true)
+        .build()
+        .unwrap();
+        let mut events: Events = Events::new(EventSettings::new());
+        let mut gl: GlGraphics = GlGraphics::new(opengl);
+        while let Some(event) = events.next(&mut window) {
+            let mut live: Vec<Input<Params>> = Vec::new();
+            if watch.as_mut().is_some_and(Watch::changed) {
+                let mut reloaded: Params = Params::default();
+                match args.apply(&mut reloaded) {
+                    Ok(()) => live.push(Input::Reload(reloaded)),
+                    Err(error) => eprintln!("{}", error),
+                }
+            }
+            if let Some(button) = event.press_args() {
+                live.push(Input::Press(button));
+            }
+            let render_args: Option<RenderArgs> = event.render_args();
+            for input in session.inputs(step, live, render_args.is_some()) {
+                match input {
+                    Input::Reload(reloaded) => {
+                        params.reload(reloaded);
+                        print!("{}", params::describe(&params));
+                    }
+                    Input::Press(Button::Keyboard(Key::C)) => {
+                        let path: PathBuf = frames::snapshot_path(
+                            Path::new("."),
+                            "webs",
+                            "toml",
+                        );
+                        let config: Config<Params> = Config {
+                            seed: Some(seed),
+                            steps,
+                            params: params.clone(),
+                        };
+                        config::save(&path, &config).unwrap();
+                        println!("{}", path.display());
+                    }
+                    Input::Press(Button::Keyboard(Key::K)) => {
+                        let path: PathBuf = frames::snapshot_path(
+                            Path::new("."),
+                            "webs",
+                            "json",
+                        );
+                        let snapshot: Snapshot<Params, State> = Snapshot {
+                            seed,
+                            step,
+                            rng: rng.clone(),
+                            params: params.clone(),
+                            state: State::save(counter, &nodes, &edges),
+                        };
+                        snapshot::save(&path, &snapshot).unwrap();
+                        println!("{}", path.display());
+                    }
+                    Input::Press(Button::Keyboard(Key::S)) => {
+                        let path: PathBuf =
+                            frames::snapshot_path(&svg_dir, "webs", "svg");
+                        let mut svg: Svg =
+                            Svg::new(params.window_edge, params.window_edge);
+                        render(&mut svg, &edges, &params);
+                        svg.write(&path).unwrap();
+                        println!("{}", path.display());
+                    }
+                    Input::Press(Button::Keyboard(Key::P)) => {
+                        let strokes: Vec<Stroke> = plot::strokes(
+                            &segments(&edges),
+                            &params.bounds(),
+                            &paper,
+                        );
+                        let (hpgl, gcode): (PathBuf, PathBuf) =
+                            plot::write(&plot_dir, "webs", &strokes).unwrap();
+                        println!("{}\n{}", hpgl.display(), gcode.display());
+                    }
+                    _ => (),
+                }
+            }
+            if let Some(args) = render_args {
+                webs_lib::step(
+                    &mut rng,
+                    uniform,
+                    &mut nodes,
+                    &mut edges,
+                    &mut counter,
+                    &params,
+                );
+                gl.draw(args.viewport(), |context, gl| {
+                    render(
+                        &mut GraphicsCanvas::new(context, gl),
+                        &edges,
+                        &params,
+                    )
+                });
+                if let Some(export) = &mut export {
+                    export
+                        .write(|context, raster| {
+                            render(
+                                &mut GraphicsCanvas::new(context, raster),
+                                &edges,
+                                &params,
+                            )
+                        })
+                        .unwrap();
+                }
+                step += 1;
+                if steps.is_some_and(|steps| steps <= step) {
+                    break;
                 }
-                webs_lib::update(&mut nodes);
-                counter += 1;
-                render(&mut gl, &args, &edges);
                 frames += 1;
                 elapsed += clock.elapsed().as_secs_f32();
                 clock = Instant::now();
@@ -181,5 +377,8 @@ fn main() {
             }
         }
     }
-    println!()
+    println!();
+    if let Some(path) = session.finish(step).unwrap() {
+        println!("{}", path.display());
+    }
 }
@@ -5,26 +5,28 @@ extern crate bencher;
 
 mod r#mod;
 
-use arrayvec::ArrayVec;
 use bencher::Bencher;
-use r#mod::{Edge, Node};
+use r#mod::{Edge, Node, Params};
 use rand::distributions::Uniform;
-use rand::rngs::ThreadRng;
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+
+const SEED: u64 = 0;
 
 fn init_insert_update(b: &mut Bencher) {
-    let mut rng: ThreadRng = rand::thread_rng();
+    let mut rng: Pcg64 = Pcg64::seed_from_u64(SEED);
+    let params: Params = Params::default();
+    let point_rng_upper: f32 = params.point_rng_upper();
     let uniform: Uniform<f32> =
-        Uniform::new_inclusive(r#mod::POINT_RNG_LOWER, r#mod::POINT_RNG_UPPER);
+        Uniform::new_inclusive(-point_rng_upper, point_rng_upper);
     b.iter(|| {
-        let mut nodes: ArrayVec<[Node; r#mod::NODES_CAP]> = ArrayVec::new();
-        let mut edges: ArrayVec<[Edge; r#mod::EDGES_CAP]> = ArrayVec::new();
+        let mut nodes: Vec<Node> = Vec::with_capacity(params.nodes_cap);
+        let mut edges: Vec<Edge> = Vec::with_capacity(params.edges_cap);
         unsafe {
             r#mod::init(&mut rng, uniform, &mut nodes, &mut edges);
-            while (nodes.len() <= r#mod::NODES_LIMIT)
-                && (edges.len() <= r#mod::EDGES_LIMIT)
-            {
+            while !params.is_full(&nodes, &edges) {
                 r#mod::insert(&mut rng, uniform, &mut nodes, &mut edges);
-                r#mod::update(&mut nodes);
+                r#mod::update(&mut nodes, &params);
             }
         };
     })
@@ -2,30 +2,28 @@
 
 use arrayvec::ArrayVec;
 use rand::distributions::Uniform;
-use rand::rngs::ThreadRng;
 use rand::Rng;
+use ranim_core::geometry::{squared_distance, Bounds, Point};
+use ranim_core::raster::SAMPLES_MAX;
+use serde::{Deserialize, Serialize};
 
-pub const WINDOW_EDGE: f64 = 800.0;
-const WINDOW_EDGE_HALF: f32 = (WINDOW_EDGE as f32) / 2.0;
-const WINDOW_EDGE_HALF_MINUS: f32 = -WINDOW_EDGE_HALF;
+const WINDOW_EDGE: f64 = 800.0;
 
-pub const ANTI_ALIAS: u8 = 4;
+const ANTI_ALIAS: u8 = 4;
 
-pub const LIGHT_GRAY: [f32; 4] = [0.95, 0.95, 0.95, 1.0];
-pub const DARK_GRAY: [f32; 4] = [0.15, 0.15, 0.15, 1.0];
-pub const CYAN: [f32; 4] = [0.5, 1.0, 0.87, 1.0];
-pub const TEAL: [f32; 4] = [0.17, 0.82, 0.76, 0.15];
+const LIGHT_GRAY: [f32; 4] = [0.95, 0.95, 0.95, 1.0];
+const DARK_GRAY: [f32; 4] = [0.15, 0.15, 0.15, 1.0];
+const CYAN: [f32; 4] = [0.5, 1.0, 0.87, 1.0];
+const TEAL: [f32; 4] = [0.17, 0.82, 0.76, 0.15];
 
-pub const LINE_WIDTH: f64 = 0.8;
-pub const RADIUS: f64 = 3.5;
-pub const RADIUS_2: f64 = RADIUS * 2.0;
-pub const RECT_PAD: f64 = 17.5;
-pub const RECT_PAD_2: f64 = RECT_PAD * 2.0;
+const LINE_WIDTH: f64 = 0.8;
+const RADIUS: f64 = 3.5;
+const RECT_PAD: f64 = 17.5;
 
-pub const INSERT_FRAME_INTERVAL: u16 = 10;
+const INSERT_FRAME_INTERVAL: u16 = 10;
 
-pub const NODES_CAP: usize = 1024;
-pub const EDGES_CAP: usize = 1024;
+const NODES_CAP: usize = 1024;
+const EDGES_CAP: usize = 1024;
 
 const NODES_INIT: usize = EDGES_INIT * 2;
 const EDGES_INIT: usize = 1;
@@ -33,18 +31,79 @@ const EDGES_INIT: usize = 1;
 const NEIGHBORS_CAP: usize = 3;
 const INTERSECTIONS_CAP: usize = 16;
 
-pub const NODES_LIMIT: usize = NODES_CAP - 2;
-pub const EDGES_LIMIT: usize = EDGES_CAP - 3;
-
-pub const POINT_RNG_UPPER: f32 = WINDOW_EDGE_HALF;
-pub const POINT_RNG_LOWER: f32 = WINDOW_EDGE_HALF_MINUS;
+/* NOTE: The most a single `insert` can add. */
+const NODES_INSERT: usize = 2;
+const EDGES_INSERT: usize = 3;
 
 const POINT_DRAG: f32 = 0.0025;
 const NEIGHBOR_DISTANCE_SQUARED: f32 = 100.0;
 
-pub struct Point {
-    pub x: f32,
-    pub y: f32,
+ranim_core::params! {
+    pub struct Params {
+        #[range(1.0..)]
+        window_edge: f64 = WINDOW_EDGE,
+        #[range(1..=SAMPLES_MAX)]
+        anti_alias: u8 = ANTI_ALIAS,
+        light_gray: [f32; 4] = LIGHT_GRAY,
+        dark_gray: [f32; 4] = DARK_GRAY,
+        cyan: [f32; 4] = CYAN,
+        teal: [f32; 4] = TEAL,
+        #[range(0.0..)]
+        line_width: f64 = LINE_WIDTH,
+        #[range(0.0..)]
+        radius: f64 = RADIUS,
+        #[range(0.0..)]
+        rect_pad: f64 = RECT_PAD,
+        #[range(NODES_INIT..)]
+        nodes_cap: usize = NODES_CAP,
+        #[range(EDGES_INIT..)]
+        edges_cap: usize = EDGES_CAP,
+        #[range(..u16::MAX)]
+        insert_frame_interval: u16 = INSERT_FRAME_INTERVAL,
+        #[range(0.0..=1.0)]
+        point_drag: f32 = POINT_DRAG,
+        #[range(0.0..)]
+        neighbor_distance_squared: f32 = NEIGHBOR_DISTANCE_SQUARED,
+    }
+}
+
+impl Params {
+    pub fn bounds(&self) -> Bounds {
+        let half: f32 = self.point_rng_upper();
+        Bounds::new(-half, -half, half, half)
+    }
+
+    /* NOTE: Everything but the window and the capacities, which fix the
+     * storage the node pointers live in, can change while running.
+     */
+    pub fn reload(&mut self, params: Self) {
+        *self = Self {
+            window_edge: self.window_edge,
+            anti_alias: self.anti_alias,
+            nodes_cap: self.nodes_cap,
+            edges_cap: self.edges_cap,
+            ..params
+        };
+    }
+
+    pub fn point_rng_upper(&self) -> f32 {
+        (self.window_edge as f32) / 2.0
+    }
+
+    pub fn is_full(&self, nodes: &[Node], edges: &[Edge]) -> bool {
+        (self.nodes_cap < (nodes.len() + NODES_INSERT))
+            || (self.edges_cap < (edges.len() + EDGES_INSERT))
+    }
+}
+
+/* NOTE: Edges and neighbors hold raw pointers into `nodes`, so it must
+ * never reallocate; callers size both `Vec`s up front from `Params`.
+ */
+macro_rules! push {
+    ($vec:expr, $value:expr $(,)?) => {{
+        assert!($vec.len() < $vec.capacity());
+        $vec.push($value);
+    }};
 }
 
 pub struct Node {
@@ -57,37 +116,137 @@ pub struct Edge {
     pub b: *mut Node,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct NodeState {
+    pub point: Point,
+    pub neighbors: Vec<usize>,
+}
+
+/* NOTE: The same graph as `nodes` and `edges`, with every pointer swapped
+ * for the index of the node it points at.
+ */
+#[derive(Serialize, Deserialize)]
+pub struct State {
+    pub counter: u16,
+    pub nodes: Vec<NodeState>,
+    pub edges: Vec<[usize; 2]>,
+}
+
+impl State {
+    pub unsafe fn save(counter: u16, nodes: &[Node], edges: &[Edge]) -> Self {
+        let base: *const Node = nodes.as_ptr();
+        let index = |node: *mut Node| node.offset_from(base) as usize;
+        Self {
+            counter,
+            nodes: nodes
+                .iter()
+                .map(|node| NodeState {
+                    point: node.point,
+                    neighbors: node
+                        .neighbors
+                        .iter()
+                        .map(|neighbor| index(*neighbor))
+                        .collect(),
+                })
+                .collect(),
+            edges: edges
+                .iter()
+                .map(|edge| [index(edge.a), index(edge.b)])
+                .collect(),
+        }
+    }
+
+    pub unsafe fn restore(
+        &self,
+        nodes: &mut Vec<Node>,
+        edges: &mut Vec<Edge>,
+    ) -> Result<(), String> {
+        let n: usize = self.nodes.len();
+        if (nodes.capacity() < n) || (edges.capacity() < self.edges.len()) {
+            return Err(format!(
+                "{} nodes and {} edges do not fit in {} and {}",
+                n,
+                self.edges.len(),
+                nodes.capacity(),
+                edges.capacity(),
+            ));
+        }
+        if self.edges.is_empty()
+            || self.nodes.iter().any(|node| {
+                (NEIGHBORS_CAP < node.neighbors.len())
+                    || node.neighbors.iter().any(|i| n <= *i)
+            })
+            || self.edges.iter().flatten().any(|i| n <= *i)
+        {
+            return Err("malformed web".to_owned());
+        }
+        nodes.clear();
+        edges.clear();
+        for node in &self.nodes {
+            push!(
+                nodes,
+                Node {
+                    point: node.point,
+                    neighbors: ArrayVec::new(),
+                }
+            );
+        }
+        let base: *mut Node = nodes.as_mut_ptr();
+        for (i, node) in self.nodes.iter().enumerate() {
+            for neighbor in &node.neighbors {
+                (*base.add(i)).neighbors.push_unchecked(base.add(*neighbor));
+            }
+        }
+        for [a, b] in &self.edges {
+            push!(
+                edges,
+                Edge {
+                    a: base.add(*a),
+                    b: base.add(*b),
+                }
+            );
+        }
+        Ok(())
+    }
+}
+
 struct Intersection<'a> {
     point: Point,
     edge: &'a mut Edge,
 }
 
 pub unsafe fn init(
-    rng: &mut ThreadRng,
+    rng: &mut impl Rng,
     uniform: Uniform<f32>,
-    nodes: &mut ArrayVec<[Node; NODES_CAP]>,
-    edges: &mut ArrayVec<[Edge; EDGES_CAP]>,
+    nodes: &mut Vec<Node>,
+    edges: &mut Vec<Edge>,
 ) {
     for _ in 0..EDGES_INIT {
-        nodes.push_unchecked(Node {
-            point: Point {
-                x: rng.sample(uniform),
-                y: rng.sample(uniform),
-            },
-            neighbors: ArrayVec::new(),
-        });
+        push!(
+            nodes,
+            Node {
+                point: Point {
+                    x: rng.sample(uniform),
+                    y: rng.sample(uniform),
+                },
+                neighbors: ArrayVec::new(),
+            }
+        );
         let a: *mut Node = nodes.last_mut().unwrap();
-        nodes.push_unchecked(Node {
-            point: Point {
-                x: rng.sample(uniform),
-                y: rng.sample(uniform),
-            },
-            neighbors: ArrayVec::new(),
-        });
+        push!(
+            nodes,
+            Node {
+                point: Point {
+                    x: rng.sample(uniform),
+                    y: rng.sample(uniform),
+                },
+                neighbors: ArrayVec::new(),
+            }
+        );
         let b: *mut Node = nodes.last_mut().unwrap();
         (*b).neighbors.push_unchecked(a);
         (*a).neighbors.push_unchecked(b);
-        edges.push_unchecked(Edge { a, b });
+        push!(edges, Edge { a, b });
     }
 }
 
@@ -118,7 +277,7 @@ fn get_intersection(
             (((x1 - x3) * (y3 - y4)) - ((y1 - y3) * (x3 - x4))) / denominator;
         let u: f32 =
             -(((x1 - x2) * (y1 - y3)) - ((y1 - y2) * (x1 - x3))) / denominator;
-        if (0.0 <= t) && (t <= 1.0) && (0.0 <= u) && (u <= 1.0) {
+        if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
             return Some(Point {
                 x: x1 + (t * (x2 - x1)),
                 y: y1 + (t * (y2 - y1)),
@@ -141,10 +300,10 @@ macro_rules! replace_neighbor {
 
 #[allow(clippy::comparison_chain, clippy::many_single_char_names)]
 pub unsafe fn insert(
-    rng: &mut ThreadRng,
+    rng: &mut impl Rng,
     uniform: Uniform<f32>,
-    nodes: &mut ArrayVec<[Node; NODES_CAP]>,
-    edges: &mut ArrayVec<[Edge; EDGES_CAP]>,
+    nodes: &mut Vec<Node>,
+    edges: &mut Vec<Edge>,
 ) {
     loop {
         let candidate_a: Point = Point {
@@ -177,22 +336,28 @@ pub unsafe fn insert(
             let edge: &mut Edge = intersection.edge;
             let a: *mut Node = edge.a;
             let b: *mut Node = edge.b;
-            nodes.push_unchecked(Node {
-                point: candidate_a,
-                neighbors: ArrayVec::new(),
-            });
+            push!(
+                nodes,
+                Node {
+                    point: candidate_a,
+                    neighbors: ArrayVec::new(),
+                }
+            );
             let q: *mut Node = nodes.last_mut().unwrap();
-            nodes.push_unchecked(Node {
-                point: intersection.point,
-                neighbors: ArrayVec::from([a, b, q]),
-            });
+            push!(
+                nodes,
+                Node {
+                    point: intersection.point,
+                    neighbors: ArrayVec::from([a, b, q]),
+                }
+            );
             let p: *mut Node = nodes.last_mut().unwrap();
             replace_neighbor!(*a, b, p);
             replace_neighbor!(*b, a, p);
             (*q).neighbors.push_unchecked(p);
-            (*edge).b = p;
-            edges.push_unchecked(Edge { a: p, b });
-            edges.push_unchecked(Edge { a: p, b: q });
+            edge.b = p;
+            push!(edges, Edge { a: p, b });
+            push!(edges, Edge { a: p, b: q });
             return;
         } else if 1 < n {
             /* NOTE: `l.a`---`l.b`    `l.a`--`p`--`l.b`
@@ -207,19 +372,25 @@ pub unsafe fn insert(
             let r_intersection: Intersection = intersections.remove(i);
             let l_edge: &mut Edge = l_intersection.edge;
             let r_edge: &mut Edge = r_intersection.edge;
-            let l_a: *mut Node = (*l_edge).a;
-            let l_b: *mut Node = (*l_edge).b;
-            let r_a: *mut Node = (*r_edge).a;
-            let r_b: *mut Node = (*r_edge).b;
-            nodes.push_unchecked(Node {
-                point: r_intersection.point,
-                neighbors: ArrayVec::new(),
-            });
+            let l_a: *mut Node = l_edge.a;
+            let l_b: *mut Node = l_edge.b;
+            let r_a: *mut Node = r_edge.a;
+            let r_b: *mut Node = r_edge.b;
+            push!(
+                nodes,
+                Node {
+                    point: r_intersection.point,
+                    neighbors: ArrayVec::new(),
+                }
+            );
             let q: *mut Node = nodes.last_mut().unwrap();
-            nodes.push_unchecked(Node {
-                point: l_intersection.point,
-                neighbors: ArrayVec::from([l_a, l_b, q]),
-            });
+            push!(
+                nodes,
+                Node {
+                    point: l_intersection.point,
+                    neighbors: ArrayVec::from([l_a, l_b, q]),
+                }
+            );
             let p: *mut Node = nodes.last_mut().unwrap();
             replace_neighbor!(*l_a, l_b, p);
             replace_neighbor!(*l_b, l_a, p);
@@ -228,24 +399,18 @@ pub unsafe fn insert(
             (*q).neighbors.push_unchecked(r_a);
             (*q).neighbors.push_unchecked(r_b);
             (*q).neighbors.push_unchecked(p);
-            (*l_edge).b = p;
-            (*r_edge).b = q;
-            edges.push_unchecked(Edge { a: p, b: l_b });
-            edges.push_unchecked(Edge { a: q, b: r_b });
-            edges.push_unchecked(Edge { a: p, b: q });
+            l_edge.b = p;
+            r_edge.b = q;
+            push!(edges, Edge { a: p, b: l_b });
+            push!(edges, Edge { a: q, b: r_b });
+            push!(edges, Edge { a: p, b: q });
             return;
         }
     }
 }
 
-fn squared_distance(a: &Point, b: &Point) -> f32 {
-    let x: f32 = a.x - b.x;
-    let y: f32 = a.y - b.y;
-    (x * x) + (y * y)
-}
-
-pub unsafe fn update(nodes: &mut ArrayVec<[Node; NODES_CAP]>) {
-    let mut updates: ArrayVec<[(usize, Point); NODES_CAP]> = ArrayVec::new();
+pub unsafe fn update(nodes: &mut [Node], params: &Params) {
+    let mut updates: Vec<(usize, Point)> = Vec::with_capacity(nodes.len());
     for i in NODES_INIT..nodes.len() {
         let node: &Node = nodes.get_unchecked(i);
         let node_point: &Point = &node.point;
@@ -256,7 +421,7 @@ pub unsafe fn update(nodes: &mut ArrayVec<[Node; NODES_CAP]>) {
         let mut update_y: f32 = 0.0;
         for neighbor in &node.neighbors {
             let neighbor_point: &Point = &(**neighbor).point;
-            if NEIGHBOR_DISTANCE_SQUARED
+            if params.neighbor_distance_squared
                 < squared_distance(node_point, neighbor_point)
             {
                 n += 1.0;
@@ -265,11 +430,11 @@ pub unsafe fn update(nodes: &mut ArrayVec<[Node; NODES_CAP]>) {
             }
         }
         if 0.0 < n {
-            updates.push_unchecked((
+            updates.push((
                 i,
                 Point {
-                    x: node_x - ((update_x / n) * POINT_DRAG),
-                    y: node_y - ((update_y / n) * POINT_DRAG),
+                    x: node_x - ((update_x / n) * params.point_drag),
+                    y: node_y - ((update_y / n) * params.point_drag),
                 },
             ));
         }
@@ -280,3 +445,23 @@ pub unsafe fn update(nodes: &mut ArrayVec<[Node; NODES_CAP]>) {
         node_point.y = update_point.y;
     }
 }
+
+pub unsafe fn step(
+    rng: &mut impl Rng,
+    uniform: Uniform<f32>,
+    nodes: &mut Vec<Node>,
+    edges: &mut Vec<Edge>,
+    counter: &mut u16,
+    params: &Params,
+) {
+    if params.is_full(nodes, edges) {
+        nodes.clear();
+        edges.clear();
+        init(rng, uniform, nodes, edges);
+    } else if params.insert_frame_interval < *counter {
+        insert(rng, uniform, nodes, edges);
+        *counter = 0;
+    }
+    update(nodes, params);
+    *counter += 1;
+}
@@ -0,0 +1,223 @@
+use ranim_core::args::Args;
+use ranim_core::params;
+use ranim_core::plot::Paper;
+use ranim_core::print::Print;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+params! {
+    pub struct Params {
+        capacity: usize = 512,
+        drag_reject: f32 = 25.0,
+    }
+}
+
+fn args(args: &[&str]) -> Result<Args, String> {
+    Args::parse(args.iter().map(|arg| (*arg).to_string()))
+}
+
+#[test]
+fn defaults() {
+    assert_eq!(args(&[]), Ok(Args::default()));
+    assert_eq!(Args::default().paper, Paper::A4);
+}
+
+#[test]
+fn output_dirs() {
+    let parsed: Args =
+        args(&["--frames", "frames", "--svg", "svg", "--plot", "plot"])
+            .unwrap();
+    assert_eq!(parsed.frames, Some(PathBuf::from("frames")));
+    assert_eq!(parsed.svg, Some(PathBuf::from("svg")));
+    assert_eq!(parsed.plot, Some(PathBuf::from("plot")));
+    assert!(args(&["--frames"]).is_err());
+    assert!(args(&["--svg"]).is_err());
+    assert!(args(&["--plot"]).is_err());
+}
+
+#[test]
+fn paper_and_margin_in_any_order() {
+    let expected: Paper = Paper {
+        margin: 5.0,
+        ..Paper::A3
+    };
+    assert_eq!(
+        args(&["--paper", "a3", "--margin", "5"]).unwrap().paper,
+        expected
+    );
+    assert_eq!(
+        args(&["--margin", "5", "--paper", "a3"]).unwrap().paper,
+        expected
+    );
+    assert!(args(&["--margin", "-1"]).is_err());
+    assert!(args(&["--paper", "letter"]).is_err());
+}
+
+#[test]
+fn seed() {
+    let parsed: Args = args(&["--seed", "42"]).unwrap();
+    assert_eq!(parsed.seed, Some(42));
+    assert_eq!(parsed.seed(), 42);
+    assert!(args(&["--seed", "-1"]).is_err());
+    assert!(args(&["--seed"]).is_err());
+}
+
+#[test]
+fn params() {
+    let parsed: Args =
+        args(&["--drag-reject", "30", "--seed", "1", "--wrap", "true"])
+            .unwrap();
+    assert_eq!(
+        parsed.params,
+        vec![
+            ("drag_reject".to_string(), "30".to_string()),
+            ("wrap".to_string(), "true".to_string()),
+        ]
+    );
+    assert!(args(&["--drag-reject"]).is_err());
+}
+
+#[test]
+fn flags_override_config() {
+    let path: PathBuf = env::temp_dir()
+        .join(format!("ranim-args-{}.toml", std::process::id()));
+    fs::write(&path, "seed = 7\nsteps = 9\n[params]\ncapacity = 64\n")
+        .unwrap();
+    let mut parsed: Args = args(&[
+        "--config",
+        path.to_str().unwrap(),
+        "--seed",
+        "1",
+        "--drag-reject",
+        "30",
+    ])
+    .unwrap();
+    let mut params: Params = Params::default();
+    parsed.apply(&mut params).unwrap();
+    assert_eq!((parsed.seed, parsed.steps), (Some(1), Some(9)));
+    assert_eq!(
+        params,
+        Params {
+            capacity: 64,
+            drag_reject: 30.0,
+        }
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn unknown_arguments_are_rejected() {
+    assert!(args(&["--unknown"]).is_err());
+    assert!(args(&["frames"]).is_err());
+    let mut params: Params = Params::default();
+    assert!(args(&["--unknown", "1"])
+        .unwrap()
+        .apply(&mut params)
+        .is_err());
+}
+
+#[test]
+fn replay_conflicts() {
+    assert!(args(&["--record", "a.json", "--replay", "b.json"]).is_err());
+    assert!(args(&["--restore", "a.json", "--record", "b.json"]).is_err());
+    assert!(args(&["--restore", "a.json", "--replay", "b.json"]).is_err());
+    assert_eq!(
+        args(&["--record", "a.json"]).unwrap().record,
+        Some(PathBuf::from("a.json"))
+    );
+}
+
+#[test]
+fn headless() {
+    let parsed: Args =
+        args(&["--headless", "--steps", "9", "--out", "out/final.svg"])
+            .unwrap();
+    assert!(parsed.headless);
+    assert_eq!(parsed.out, Some(PathBuf::from("out/final.svg")));
+    assert!(args(&["--headless"]).is_err());
+    assert!(args(&["--out", "final.png"]).is_err());
+    assert!(args(&["--headless", "--out", "final.jpg"]).is_err());
+    assert!(args(&["--headless", "--out", "a.png", "--frames", "f"]).is_err());
+    let mut params: Params = Params::default();
+    assert!(args(&["--headless", "--out", "final.png"])
+        .unwrap()
+        .apply(&mut params)
+        .is_err());
+}
+
+#[test]
+fn batch() {
+    let parsed: Args = args(&[
+        "--batch",
+        "out",
+        "--steps",
+        "9",
+        "--seeds",
+        "3..5",
+        "--sweep",
+        "drag-reject=10..40:4",
+    ])
+    .unwrap();
+    assert_eq!(parsed.seeds, Some(3..5));
+    assert_eq!(parsed.sweep.as_ref().unwrap().count, 4);
+    assert_eq!(
+        parsed
+            .batch(&Params::default(), |_| (16, 16))
+            .unwrap()
+            .tiles
+            .len(),
+        8
+    );
+    assert!(args(&["--seeds", "1..4"]).is_err());
+    assert!(args(&["--batch", "out", "--seeds", "4..4"]).is_err());
+    assert!(args(&["--batch", "out", "--headless"]).is_err());
+}
+
+#[test]
+fn print() {
+    let parsed: Args = args(&[
+        "--headless",
+        "--out",
+        "final.png",
+        "--print-cm",
+        "10",
+        "--supersample",
+        "2",
+    ])
+    .unwrap();
+    let print: Print = parsed.print(800.0, 400.0, 4);
+    assert_eq!(print.size, [1181, 591]);
+    assert_eq!(print.dpi, Some(300.0));
+    assert_eq!(print.supersample, 2);
+    assert_eq!(
+        args(&["--headless", "--out", "a.svg", "--print-size", "400"])
+            .unwrap()
+            .print(800.0, 800.0, 4)
+            .size,
+        [400, 400]
+    );
+    assert!(args(&["--print-size", "400"]).is_err());
+    for (flag, value) in &[
+        ("--print-size", "0"),
+        ("--print-size", "100000"),
+        ("--print-cm", "inf"),
+        ("--print-cm", "NaN"),
+        ("--dpi", "inf"),
+    ] {
+        assert!(args(&["--headless", "--out", "a.png", flag, value]).is_err());
+    }
+    assert!(args(&[
+        "--headless",
+        "--out",
+        "a.png",
+        "--print-size",
+        "400",
+        "--print-cm",
+        "10",
+    ])
+    .is_err());
+    assert!(
+        args(&["--headless", "--out", "a.png", "--supersample", "0"]).is_err()
+    );
+}
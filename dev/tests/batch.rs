@@ -0,0 +1,116 @@
+use ranim_core::batch;
+use ranim_core::batch::{Plan, Sweep};
+use ranim_core::params;
+use ranim_core::raster::Raster;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+params! {
+    pub struct Params {
+        capacity: usize = 512,
+        drag_reject: f32 = 25.0,
+    }
+}
+
+fn size(_: &Params) -> (u32, u32) {
+    (16, 8)
+}
+
+#[test]
+fn sweep_values_include_both_ends() {
+    let sweep: Sweep = Sweep::parse("drag-reject=10..40:4").unwrap();
+    assert_eq!(sweep.name, "drag_reject");
+    assert_eq!(sweep.values(), vec!["10", "20", "30", "40"]);
+    assert_eq!(Sweep::parse("radius=0..0.3:4").unwrap().values()[1], "0.1");
+    assert_eq!(Sweep::parse("radius=2..9:1").unwrap().values(), vec!["2"]);
+    for bad in &["radius", "radius=1..2", "radius=1:2", "radius=1..2:0"] {
+        assert!(Sweep::parse(bad).is_err());
+    }
+}
+
+#[test]
+fn plan_has_a_row_per_seed_and_a_column_per_value() {
+    let sweep: Sweep = Sweep::parse("drag-reject=10..40:4").unwrap();
+    let plan: Plan<Params> = batch::plan(
+        PathBuf::new(),
+        9,
+        &[3, 4],
+        Some(&sweep),
+        &Params::default(),
+        size,
+    )
+    .unwrap();
+    assert_eq!((plan.columns, plan.tiles.len()), (4, 8));
+    assert_eq!(plan.tiles[5].seed, 4);
+    assert_eq!(plan.tiles[5].params.drag_reject, 20.0);
+    assert_eq!(plan.tiles[5].label, "#5 seed=4 drag_reject=20");
+    let plan: Plan<Params> = batch::plan(
+        PathBuf::new(),
+        9,
+        &[1, 2, 3, 4, 5],
+        None,
+        &Params::default(),
+        size,
+    )
+    .unwrap();
+    assert_eq!(plan.columns, 3);
+    let sweep: Sweep = Sweep::parse("capacity=1..2:3").unwrap();
+    assert!(batch::plan(
+        PathBuf::new(),
+        9,
+        &[1],
+        Some(&sweep),
+        &Params::default(),
+        size,
+    )
+    .is_err());
+}
+
+#[test]
+fn plan_rejects_a_sweep_that_changes_the_tile_size() {
+    let sweep: Sweep = Sweep::parse("capacity=16..32:2").unwrap();
+    let params: Params = Params::default();
+    let plan: Plan<Params> =
+        batch::plan(PathBuf::new(), 9, &[1], Some(&sweep), &params, size)
+            .unwrap();
+    assert_eq!(plan.size, (16, 8));
+    let size = |params: &Params| (params.capacity as u32, 8);
+    assert!(
+        batch::plan(PathBuf::new(), 9, &[1], Some(&sweep), &params, size)
+            .is_err()
+    );
+}
+
+#[test]
+fn run_writes_tiles_sheet_and_manifest() {
+    let dir: PathBuf =
+        env::temp_dir().join(format!("ranim-batch-{}", std::process::id()));
+    let plan: Plan<Params> = batch::plan(
+        dir.clone(),
+        9,
+        &[1, 2, 3],
+        None,
+        &Params::default(),
+        size,
+    )
+    .unwrap();
+    let black: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+    let white: [f32; 4] = [1.0; 4];
+    let path: PathBuf = batch::run(&plan, "test", [black, white], |_| {
+        let mut raster: Raster = Raster::new(16, 8, 1);
+        raster.draw(|_, raster| graphics::clear(white, raster));
+        raster
+    })
+    .unwrap();
+    assert_eq!(path, dir.join("test-sheet.png"));
+    assert!(path.exists());
+    for file in &["000.png", "001.png", "002.png"] {
+        assert!(dir.join(file).exists());
+    }
+    let manifest: String =
+        fs::read_to_string(dir.join("manifest.toml")).unwrap();
+    assert!(manifest.contains("sketch = \"test\""));
+    assert!(manifest.contains("seed = 3"));
+    fs::remove_dir_all(dir).unwrap();
+}
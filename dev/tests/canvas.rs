@@ -0,0 +1,79 @@
+use ranim_core::canvas::{Canvas, GraphicsCanvas, Style};
+use ranim_core::raster::Raster;
+use ranim_core::svg::Svg;
+
+const WIDTH: u32 = 32;
+const HEIGHT: u32 = 16;
+
+const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+fn pixel(raster: &Raster, x: u32, y: u32) -> [u8; 4] {
+    let i: usize = (((y * raster.width()) + x) * 4) as usize;
+    let mut pixel: [u8; 4] = [0; 4];
+    pixel.copy_from_slice(&raster.pixels()[i..(i + 4)]);
+    pixel
+}
+
+fn frame(canvas: &mut impl Canvas) {
+    canvas.clear(BLACK);
+    canvas.rectangle(&Style::fill(RED), [-16.0, -8.0, 4.0, 4.0]);
+    canvas.circle(&Style::fill(WHITE), [0.0, 0.0], 2.0);
+    canvas.line(&Style::stroke(WHITE, 2.0), [4.0, 4.0, 12.0, 4.0]);
+    canvas.polyline(
+        &Style::stroke(WHITE, 2.0),
+        &[[-12.0, 4.0], [-8.0, 4.0], [-8.0, 7.0]],
+    );
+}
+
+fn raster() -> Raster {
+    let mut raster: Raster = Raster::new(WIDTH, HEIGHT, 4);
+    raster.draw(|context, raster| {
+        frame(&mut GraphicsCanvas::new(context, raster))
+    });
+    raster
+}
+
+#[test]
+fn origin_is_centered() {
+    let raster: Raster = raster();
+    assert_eq!(pixel(&raster, 0, 0), [255, 0, 0, 255]);
+    assert_eq!(pixel(&raster, 3, 3), [255, 0, 0, 255]);
+    assert_eq!(pixel(&raster, 4, 4), [0, 0, 0, 255]);
+    assert_eq!(pixel(&raster, 16, 8), [255, 255, 255, 255]);
+    assert_eq!(pixel(&raster, 15, 7), [255, 255, 255, 255]);
+}
+
+#[test]
+fn stroke_width_is_the_full_width() {
+    let raster: Raster = raster();
+    for x in 21..27 {
+        assert_eq!(pixel(&raster, x, 11), [255, 255, 255, 255]);
+        assert_eq!(pixel(&raster, x, 12), [255, 255, 255, 255]);
+        assert_eq!(pixel(&raster, x, 10), [0, 0, 0, 255]);
+        assert_eq!(pixel(&raster, x, 13), [0, 0, 0, 255]);
+    }
+}
+
+#[test]
+fn polylines_are_joined() {
+    let raster: Raster = raster();
+    for x in 5..8 {
+        assert_eq!(pixel(&raster, x, 12), [255, 255, 255, 255]);
+    }
+    assert_eq!(pixel(&raster, 8, 12), [255, 255, 255, 255]);
+    assert_eq!(pixel(&raster, 8, 14), [255, 255, 255, 255]);
+}
+
+#[test]
+fn one_frame_draws_to_every_canvas() {
+    let mut svg: Svg = Svg::new(WIDTH as f64, HEIGHT as f64);
+    frame(&mut svg);
+    let document: String = svg.to_document();
+    assert_eq!(document.matches("<rect").count(), 2);
+    assert_eq!(document.matches("<circle").count(), 1);
+    assert_eq!(document.matches("<line").count(), 1);
+    assert_eq!(document.matches("<polyline").count(), 1);
+    assert!(document.contains("stroke-width=\"2\""));
+}
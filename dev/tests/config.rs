@@ -0,0 +1,92 @@
+use ranim_core::config;
+use ranim_core::config::{Config, Watch};
+use ranim_core::params;
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+params! {
+    pub struct Params {
+        edge: f64 = 800.0,
+        capacity: usize = 512,
+        cyan: [f32; 4] = [0.5, 1.0, 0.87, 0.5],
+    }
+}
+
+fn config() -> Config<Params> {
+    Config {
+        seed: Some(42),
+        steps: Some(500),
+        params: Params {
+            capacity: 64,
+            ..Params::default()
+        },
+    }
+}
+
+#[test]
+fn toml_round_trip() {
+    let path: &Path = Path::new("preset.toml");
+    let text: String = config::to_string(path, &config()).unwrap();
+    assert!(text.contains("cyan = [0.5, 1.0, 0.87, 0.5]"));
+    assert_eq!(config::parse(path, &text), Ok(config()));
+}
+
+#[test]
+fn json_round_trip() {
+    let path: &Path = Path::new("preset.json");
+    let text: String = config::to_string(path, &config()).unwrap();
+    assert!(text.starts_with('{'));
+    assert_eq!(config::parse(path, &text), Ok(config()));
+}
+
+#[test]
+fn missing_fields_take_defaults() {
+    let path: &Path = Path::new("preset.toml");
+    let parsed: Config<Params> =
+        config::parse(path, "[params]\ncapacity = 64\n").unwrap();
+    assert_eq!(parsed.seed, None);
+    assert_eq!(parsed.steps, None);
+    assert_eq!(parsed.params, config().params);
+    assert_eq!(config::parse(path, ""), Ok(Config::<Params>::default()));
+}
+
+#[test]
+fn unknown_fields_are_rejected() {
+    let path: &Path = Path::new("preset.toml");
+    assert!(config::parse::<Params>(path, "sed = 1\n").is_err());
+    assert!(config::parse::<Params>(path, "[params]\nedges = 1\n").is_err());
+}
+
+#[test]
+fn save_and_load() {
+    let dir: PathBuf =
+        env::temp_dir().join(format!("ranim-config-{}", std::process::id()));
+    let path: PathBuf = dir.join("nested").join("preset.json");
+    config::save(&path, &config()).unwrap();
+    assert_eq!(config::load(&path), Ok(config()));
+    assert!(config::load::<Params>(&dir.join("missing.toml")).is_err());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn watch_sees_new_modification_times() {
+    let path: PathBuf = env::temp_dir()
+        .join(format!("ranim-watch-{}.toml", std::process::id()));
+    let mut watch: Watch = Watch::new(path.clone());
+    assert!(!watch.changed());
+    config::save(&path, &config()).unwrap();
+    assert!(watch.changed());
+    assert!(!watch.changed());
+    File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(60))
+        .unwrap();
+    assert!(watch.changed());
+    fs::remove_file(&path).unwrap();
+    assert!(!watch.changed());
+}
@@ -0,0 +1,65 @@
+use png::{Decoder, OutputInfo, Reader};
+use ranim_core::canvas::Canvas;
+use ranim_core::frames::{Format, Frames};
+use ranim_core::print::Print;
+use ranim_core::write_image;
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+fn read_png(path: &PathBuf) -> (OutputInfo, Vec<u8>) {
+    let (info, mut reader): (OutputInfo, Reader<File>) =
+        Decoder::new(File::open(path).unwrap()).read_info().unwrap();
+    let mut pixels: Vec<u8> = vec![0; info.buffer_size()];
+    reader.next_frame(&mut pixels).unwrap();
+    (info, pixels)
+}
+
+#[test]
+fn frames_are_numbered_pngs() {
+    let dir: PathBuf = env::temp_dir()
+        .join(format!("ranim-frames-{}", std::process::id()))
+        .join("nested");
+    let mut frames: Frames = Frames::new(dir.clone(), 8, 4, 2).unwrap();
+    for _ in 0..3 {
+        frames
+            .write(|_, raster| graphics::clear(RED, raster))
+            .unwrap();
+    }
+    assert_eq!(frames.index(), 3);
+    for index in 0..3 {
+        let path: PathBuf = dir.join(format!("{:06}.png", index));
+        let (info, pixels): (OutputInfo, Vec<u8>) = read_png(&path);
+        assert_eq!((info.width, info.height), (8, 4));
+        assert!(pixels.chunks(4).all(|pixel| pixel == [255, 0, 0, 255]));
+    }
+    fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn write_image_picks_the_format_from_the_extension() {
+    let dir: PathBuf = env::temp_dir()
+        .join(format!("ranim-image-{}", std::process::id()))
+        .join("nested");
+    let print: Print = Print::new(8.0, 4.0, 2);
+    for name in &["final.png", "final.svg"] {
+        let path: PathBuf = dir.join(name);
+        write_image!(&path, &print, |canvas| canvas.clear(RED)).unwrap();
+    }
+    let (info, pixels): (OutputInfo, Vec<u8>) =
+        read_png(&dir.join("final.png"));
+    assert_eq!((info.width, info.height), (8, 4));
+    assert!(pixels.chunks(4).all(|pixel| pixel == [255, 0, 0, 255]));
+    assert!(fs::read_to_string(dir.join("final.svg"))
+        .unwrap()
+        .starts_with("<svg"));
+    assert!(write_image!(&dir.join("final.jpg"), &print, |canvas| {
+        canvas.clear(RED)
+    })
+    .is_err());
+    assert_eq!(Format::of(Path::new("a.svg")), Ok(Format::Svg));
+    fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+}
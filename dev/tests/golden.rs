@@ -0,0 +1,106 @@
+#![allow(dead_code)]
+
+#[path = "../src/growth_lib/mod.rs"]
+mod growth_lib;
+#[path = "../src/webs_lib/mod.rs"]
+mod webs_lib;
+
+use rand::distributions::Uniform;
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+use ranim_core::kdtree_lib::KdTree;
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+const SEED: u64 = 0;
+const STEPS: usize = 500;
+
+/* NOTE: Run with `RANIM_BLESS=1` to rewrite the golden files after a
+ * deliberate change in behaviour.
+ */
+fn check(name: &str, state: &impl Serialize) {
+    let path: PathBuf = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.json", name));
+    let text: String = serde_json::to_string_pretty(state).unwrap() + "\n";
+    if env::var_os("RANIM_BLESS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, text).unwrap();
+        return;
+    }
+    let golden: String = fs::read_to_string(&path).unwrap();
+    assert!(golden == text, "{} no longer matches", path.display());
+}
+
+fn growth(params: &growth_lib::Params) -> Vec<growth_lib::Node> {
+    let mut rng: Pcg64 = Pcg64::seed_from_u64(SEED);
+    let point_rng_upper: f32 = params.point_rng_upper();
+    let uniform_init: Uniform<f32> =
+        Uniform::new_inclusive(-point_rng_upper, point_rng_upper);
+    let uniform_walk: Uniform<f32> =
+        Uniform::new_inclusive(-params.walk_rng, params.walk_rng);
+    let mut tree: KdTree = KdTree::new();
+    tree.set_rebalance_threshold(Some(params.rebalance_threshold));
+    let mut nodes: Vec<growth_lib::Node> = Vec::with_capacity(params.capacity);
+    growth_lib::init_nodes(
+        &mut rng,
+        uniform_init,
+        &mut nodes,
+        &mut tree,
+        params,
+    );
+    for _ in 0..STEPS {
+        growth_lib::update_nodes(
+            &mut rng,
+            uniform_walk,
+            &mut nodes,
+            &mut tree,
+            params,
+        );
+    }
+    nodes
+}
+
+#[test]
+fn growth_update_nodes() {
+    check("growth", &growth(&growth_lib::Params::default()));
+}
+
+#[test]
+fn growth_update_nodes_wrapped() {
+    let params: growth_lib::Params = growth_lib::Params {
+        wrap: true,
+        ..growth_lib::Params::default()
+    };
+    check("growth-wrap", &growth(&params));
+}
+
+#[test]
+fn webs_step() {
+    let params: webs_lib::Params = webs_lib::Params::default();
+    let mut rng: Pcg64 = Pcg64::seed_from_u64(SEED);
+    let point_rng_upper: f32 = params.point_rng_upper();
+    let uniform: Uniform<f32> =
+        Uniform::new_inclusive(-point_rng_upper, point_rng_upper);
+    let mut nodes: Vec<webs_lib::Node> = Vec::with_capacity(params.nodes_cap);
+    let mut edges: Vec<webs_lib::Edge> = Vec::with_capacity(params.edges_cap);
+    let mut counter: u16 = 0;
+    let state: webs_lib::State = unsafe {
+        webs_lib::init(&mut rng, uniform, &mut nodes, &mut edges);
+        for _ in 0..STEPS {
+            webs_lib::step(
+                &mut rng,
+                uniform,
+                &mut nodes,
+                &mut edges,
+                &mut counter,
+                &params,
+            );
+        }
+        webs_lib::State::save(counter, &nodes, &edges)
+    };
+    check("webs", &state);
+}
@@ -0,0 +1,90 @@
+use rand::distributions::Uniform;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use ranim_core::geometry::{squared_distance, Bounds, Point};
+use ranim_core::grid::Grid;
+
+const SEEDS: u64 = 8;
+const CAPACITY: usize = 128;
+const CELL_SIZES: [f32; 3] = [1.0, 10.0, 300.0];
+const RADII_SQUARED: [f32; 4] = [0.0, 1.0, 100.0, 10_000.0];
+
+const BOUNDS: Bounds = Bounds::new(-100.0, -100.0, 100.0, 100.0);
+
+fn random_points(rng: &mut StdRng, n: usize, edge: f32) -> Vec<Point> {
+    let uniform: Uniform<f32> = Uniform::new_inclusive(-edge, edge);
+    (0..n)
+        .map(|_| Point::new(rng.sample(uniform), rng.sample(uniform)))
+        .collect()
+}
+
+fn search(grid: &Grid, point: &Point, radius_squared: f32) -> Vec<usize> {
+    let mut neighbors: Vec<usize> = Vec::new();
+    grid.search(point, radius_squared, &mut neighbors);
+    neighbors.sort_unstable();
+    neighbors
+}
+
+fn scan(points: &[Point], point: &Point, radius_squared: f32) -> Vec<usize> {
+    (0..points.len())
+        .filter(|i| squared_distance(point, &points[*i]) <= radius_squared)
+        .collect()
+}
+
+/* NOTE: Points past `BOUNDS` stretch the grid, so some of these land
+ * outside of it on purpose.
+ */
+#[test]
+fn search_matches_a_linear_scan() {
+    for seed in 0..SEEDS {
+        let mut rng: StdRng = StdRng::seed_from_u64(seed);
+        let points: Vec<Point> = random_points(&mut rng, CAPACITY, 150.0);
+        let queries: Vec<Point> = random_points(&mut rng, 16, 200.0);
+        for cell_size in &CELL_SIZES {
+            let grid: Grid = Grid::build(&points, BOUNDS, *cell_size);
+            assert_eq!(grid.len(), points.len());
+            for query in &queries {
+                for radius_squared in &RADII_SQUARED {
+                    assert_eq!(
+                        search(&grid, query, *radius_squared),
+                        scan(&points, query, *radius_squared),
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn cell_size_applies_on_rebuild() {
+    let mut rng: StdRng = StdRng::seed_from_u64(0);
+    let points: Vec<Point> = random_points(&mut rng, CAPACITY, 100.0);
+    let mut grid: Grid = Grid::build(&points, BOUNDS, 10.0);
+    grid.set_cell_size(25.0);
+    grid.rebuild(&points, BOUNDS);
+    assert_eq!(grid.cell_size(), 25.0);
+    let origin: Point = Point::new(0.0, 0.0);
+    assert_eq!(
+        search(&grid, &origin, 2500.0),
+        scan(&points, &origin, 2500.0)
+    );
+}
+
+#[test]
+fn empty_grid_finds_nothing() {
+    let grid: Grid = Grid::build(&[], BOUNDS, 10.0);
+    assert!(grid.is_empty());
+    assert!(search(&grid, &Point::new(0.0, 0.0), 10_000.0).is_empty());
+}
+
+#[test]
+#[should_panic]
+fn zero_cell_size_is_rejected() {
+    Grid::build(&[Point::new(0.0, 0.0)], BOUNDS, 0.0);
+}
+
+#[test]
+#[should_panic]
+fn nan_cell_size_is_rejected() {
+    Grid::new(10.0).set_cell_size(f32::NAN);
+}
@@ -0,0 +1,266 @@
+use rand::distributions::Uniform;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use ranim_core::geometry::{squared_distance, Bounds, Coordinates, Point};
+use ranim_core::kdtree_lib;
+use ranim_core::kdtree_lib::{KdTree, Neighbor, StackKdTree, Tree};
+
+const SEEDS: u64 = 8;
+const CAPACITY: usize = 128;
+const K: [usize; 4] = [1, 4, 16, CAPACITY + 1];
+
+const BOUNDS: Bounds = Bounds::new(-100.0, -100.0, 100.0, 100.0);
+
+fn random_points(rng: &mut StdRng, n: usize) -> Vec<Point> {
+    let uniform: Uniform<f32> = Uniform::new_inclusive(-100.0, 100.0);
+    (0..n)
+        .map(|_| Point::new(rng.sample(uniform), rng.sample(uniform)))
+        .collect()
+}
+
+fn distances(neighbors: &[Neighbor]) -> Vec<f32> {
+    neighbors
+        .iter()
+        .map(|neighbor| neighbor.squared_distance)
+        .collect()
+}
+
+fn sorted(mut indices: Vec<usize>) -> Vec<usize> {
+    indices.sort_unstable();
+    indices
+}
+
+fn scan(points: &[Point], point: &Point, k: usize) -> Vec<f32> {
+    let mut distances: Vec<f32> = points
+        .iter()
+        .map(|other| squared_distance(point, other))
+        .collect();
+    distances.sort_by(f32::total_cmp);
+    distances.truncate(k);
+    distances
+}
+
+#[test]
+fn nearest_matches_a_linear_scan() {
+    for seed in 0..SEEDS {
+        let mut rng: StdRng = StdRng::seed_from_u64(seed);
+        let points: Vec<Point> = random_points(&mut rng, CAPACITY);
+        let tree: KdTree = KdTree::build(&points, BOUNDS);
+        for query in &random_points(&mut rng, 16) {
+            let neighbor: Neighbor = tree.nearest(query).unwrap();
+            assert_eq!(
+                neighbor.squared_distance,
+                squared_distance(query, &points[neighbor.index]),
+            );
+            assert_eq!(
+                vec![neighbor.squared_distance],
+                scan(&points, query, 1)
+            );
+        }
+    }
+}
+
+#[test]
+fn k_nearest_matches_a_linear_scan() {
+    for seed in 0..SEEDS {
+        let mut rng: StdRng = StdRng::seed_from_u64(seed);
+        let points: Vec<Point> = random_points(&mut rng, CAPACITY);
+        let tree: KdTree = KdTree::build(&points, BOUNDS);
+        for query in &random_points(&mut rng, 16) {
+            for k in &K {
+                assert_eq!(
+                    distances(&tree.k_nearest(query, *k)),
+                    scan(&points, query, *k),
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn empty_tree_has_no_neighbors() {
+    let tree: KdTree = KdTree::build(&[], BOUNDS);
+    let origin: Point = Point::new(0.0, 0.0);
+    assert!(tree.nearest(&origin).is_none());
+    assert!(tree.k_nearest(&origin, 4).is_empty());
+    let tree: KdTree = KdTree::build(&[origin], BOUNDS);
+    assert!(tree.k_nearest(&origin, 0).is_empty());
+}
+
+/* NOTE: `points` mirrors the tree, with `None` for removed indices. */
+fn check_live(tree: &KdTree, points: &[Option<Point>], queries: &[Point]) {
+    let live: Vec<Point> = points.iter().flatten().copied().collect();
+    assert_eq!(tree.len(), live.len());
+    for query in queries {
+        for k in &K {
+            assert_eq!(
+                distances(&tree.k_nearest(query, *k)),
+                scan(&live, query, *k),
+            );
+        }
+        for radius_squared in &[0.0, 100.0, 2500.0] {
+            let mut neighbors: Vec<usize> = Vec::new();
+            tree.search(query, *radius_squared, &mut neighbors);
+            let expected: Vec<usize> = points
+                .iter()
+                .enumerate()
+                .filter_map(|(index, point)| {
+                    point.filter(|point| {
+                        squared_distance(query, point) <= *radius_squared
+                    })?;
+                    Some(index)
+                })
+                .collect();
+            assert_eq!(sorted(neighbors), expected);
+        }
+    }
+}
+
+#[test]
+fn updates_match_a_linear_scan() {
+    let uniform_walk: Uniform<f32> = Uniform::new_inclusive(-10.0, 10.0);
+    for threshold in &[None, Some(0.5)] {
+        for seed in 0..SEEDS {
+            let mut rng: StdRng = StdRng::seed_from_u64(seed);
+            let mut points: Vec<Point> = random_points(&mut rng, CAPACITY);
+            let mut tree: KdTree = KdTree::build(&points, BOUNDS);
+            tree.set_rebalance_threshold(*threshold);
+            for _ in 0..8 {
+                for (i, point) in points.iter_mut().enumerate() {
+                    let from: Point = *point;
+                    point.x += rng.sample(uniform_walk);
+                    point.y += rng.sample(uniform_walk);
+                    assert!(tree.update(i, &from, *point));
+                }
+                let live: Vec<Option<Point>> =
+                    points.iter().copied().map(Some).collect();
+                check_live(&tree, &live, &random_points(&mut rng, 8));
+            }
+        }
+    }
+}
+
+#[test]
+fn interior_points_move_in_place() {
+    for seed in 0..SEEDS {
+        let mut rng: StdRng = StdRng::seed_from_u64(seed);
+        let mut points: Vec<Point> = random_points(&mut rng, CAPACITY);
+        let mut tree: KdTree = KdTree::build(&points, BOUNDS);
+        let moves: Vec<(usize, Point)> = tree
+            .trees()
+            .iter()
+            .filter_map(|parent| {
+                let left: &Tree = &tree.trees()[parent.left?];
+                let right: &Tree = &tree.trees()[parent.right?];
+                let (axis, other): (usize, usize) =
+                    (parent.axis, 1 - parent.axis);
+                let mut point: Point = parent.point;
+                point.set(
+                    axis,
+                    (left.bounds.upper.get(axis)
+                        + right.bounds.lower.get(axis))
+                        / 2.0,
+                );
+                point.set(
+                    other,
+                    (point.get(other) + parent.bounds.lower.get(other)) / 2.0,
+                );
+                Some((parent.index, point))
+            })
+            .collect();
+        assert!(!moves.is_empty());
+        for (index, point) in moves {
+            assert!(tree.update(index, &points[index], point));
+            points[index] = point;
+        }
+        assert_eq!(tree.trees().len(), CAPACITY);
+        let live: Vec<Option<Point>> =
+            points.iter().copied().map(Some).collect();
+        check_live(&tree, &live, &random_points(&mut rng, 8));
+    }
+}
+
+#[test]
+fn updates_without_a_threshold_reclaim_removed_points() {
+    let uniform_walk: Uniform<f32> = Uniform::new_inclusive(-10.0, 10.0);
+    let mut rng: StdRng = StdRng::seed_from_u64(0);
+    let mut points: Vec<Point> = random_points(&mut rng, kdtree_lib::CAPACITY);
+    let mut tree: StackKdTree = StackKdTree::build(&points, BOUNDS);
+    for _ in 0..64 {
+        for (i, point) in points.iter_mut().enumerate() {
+            let from: Point = *point;
+            point.x += rng.sample(uniform_walk);
+            point.y += rng.sample(uniform_walk);
+            assert!(tree.update(i, &from, *point));
+        }
+    }
+    assert_eq!(tree.len(), points.len());
+    for query in &random_points(&mut rng, 8) {
+        for k in &K {
+            assert_eq!(
+                distances(&tree.k_nearest(query, *k)),
+                scan(&points, query, *k),
+            );
+        }
+    }
+}
+
+#[test]
+fn inserts_and_removes_match_a_linear_scan() {
+    let uniform_far: Uniform<f32> = Uniform::new_inclusive(-300.0, 300.0);
+    for seed in 0..SEEDS {
+        let mut rng: StdRng = StdRng::seed_from_u64(seed);
+        let mut points: Vec<Option<Point>> = random_points(&mut rng, CAPACITY)
+            .into_iter()
+            .map(Some)
+            .collect();
+        let live: Vec<Point> = points.iter().flatten().copied().collect();
+        let mut tree: KdTree = KdTree::build(&live, BOUNDS);
+        tree.set_rebalance_threshold(Some(0.5));
+        for i in 0..32 {
+            let point: Point =
+                Point::new(rng.sample(uniform_far), rng.sample(uniform_far));
+            tree.insert(points.len(), point);
+            points.push(Some(point));
+            let index: usize = i * 3;
+            assert!(tree.remove(index, &points[index].take().unwrap()));
+        }
+        check_live(&tree, &points, &random_points(&mut rng, 8));
+    }
+}
+
+#[test]
+fn sorted_inserts_do_not_grow_a_chain() {
+    let n: usize = 1 << 16;
+    let edge: f32 = n as f32;
+    let uniform: Uniform<f32> = Uniform::new_inclusive(0.0, edge);
+    let mut rng: StdRng = StdRng::seed_from_u64(0);
+    let mut points: Vec<Option<Point>> = Vec::with_capacity(n);
+    let mut tree: KdTree =
+        KdTree::build(&[], Bounds::new(0.0, 0.0, edge, edge));
+    for i in 0..n {
+        let point: Point = Point::new(i as f32, i as f32);
+        tree.insert(i, point);
+        points.push(Some(point));
+        if (i % 3) == 0 {
+            let index: usize = i / 2;
+            assert!(tree.remove(index, &points[index].take().unwrap()));
+        }
+    }
+    let queries: Vec<Point> = (0..8)
+        .map(|_| Point::new(rng.sample(uniform), rng.sample(uniform)))
+        .collect();
+    check_live(&tree, &points, &queries);
+}
+
+#[test]
+fn missing_indices_are_not_removed_or_updated() {
+    let origin: Point = Point::new(0.0, 0.0);
+    let point: Point = Point::new(1.0, 1.0);
+    let mut tree: KdTree = KdTree::build(&[origin], BOUNDS);
+    assert!(!tree.remove(1, &origin));
+    assert!(!tree.update(1, &origin, point));
+    assert!(tree.remove(0, &origin));
+    assert!(!tree.remove(0, &origin));
+    assert!(tree.is_empty());
+}
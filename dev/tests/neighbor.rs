@@ -0,0 +1,359 @@
+use rand::distributions::Uniform;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use ranim_core::geometry::{
+    periodic_squared_distance, Bounds, Coordinates, Point,
+};
+use ranim_core::grid::Grid;
+use ranim_core::kdtree_lib::{KdTree, Neighbor};
+use ranim_core::neighbor::{BruteForce, NeighborIndex};
+
+const SEEDS: u64 = 16;
+const CAPACITY: usize = 256;
+const RADII_SQUARED: [f32; 4] = [0.0, 1.0, 100.0, 10_000.0];
+const K: [usize; 3] = [1, 4, 16];
+
+const BOUNDS: Bounds = Bounds::new(-100.0, -100.0, 100.0, 100.0);
+
+fn random_points(
+    rng: &mut StdRng,
+    uniform: Uniform<f32>,
+    n: usize,
+) -> Vec<Point> {
+    (0..n)
+        .map(|_| Point::new(rng.sample(uniform), rng.sample(uniform)))
+        .collect()
+}
+
+fn sorted(mut indices: Vec<usize>) -> Vec<usize> {
+    indices.sort_unstable();
+    indices
+}
+
+fn distances(neighbors: &[Neighbor]) -> Vec<f32> {
+    neighbors
+        .iter()
+        .map(|neighbor| neighbor.squared_distance)
+        .collect()
+}
+
+fn search<P: Coordinates>(
+    index: &impl NeighborIndex<P>,
+    point: &P,
+    radius_squared: f32,
+) -> Vec<usize> {
+    let mut neighbors: Vec<usize> = Vec::new();
+    index.search(point, radius_squared, &mut neighbors);
+    sorted(neighbors)
+}
+
+fn check_index<P: Coordinates>(
+    index: &impl NeighborIndex<P>,
+    brute: &BruteForce<P>,
+    queries: &[P],
+) {
+    for query in queries {
+        for radius_squared in &RADII_SQUARED {
+            assert_eq!(
+                search(index, query, *radius_squared),
+                search(brute, query, *radius_squared),
+            );
+        }
+        assert_eq!(
+            index
+                .nearest(query)
+                .map(|neighbor| neighbor.squared_distance),
+            brute
+                .nearest(query)
+                .map(|neighbor| neighbor.squared_distance),
+        );
+    }
+}
+
+fn check_tree<P: Coordinates>(
+    tree: &KdTree<P>,
+    brute: &BruteForce<P>,
+    queries: &[P],
+) {
+    check_index(tree, brute, queries);
+    for query in queries {
+        for k in &K {
+            assert_eq!(
+                distances(&tree.k_nearest(query, *k)),
+                distances(&brute.k_nearest(query, *k)),
+            );
+        }
+    }
+}
+
+fn check_within(tree: &KdTree, brute: &BruteForce, queries: &[Point]) {
+    for query in queries {
+        for edge in &[0.0, 1.0, 25.0] {
+            let bounds: Bounds = Bounds::new(
+                query.x - edge,
+                query.y - edge,
+                query.x + edge,
+                query.y + edge,
+            );
+            let mut expected: Vec<usize> = Vec::new();
+            let mut neighbors: Vec<usize> = Vec::new();
+            brute.within(&bounds, &mut expected);
+            tree.within(&bounds, &mut neighbors);
+            assert_eq!(sorted(neighbors), sorted(expected));
+        }
+    }
+}
+
+fn check_points(points: &[Point], queries: &[Point]) {
+    let brute: BruteForce = BruteForce::build(points);
+    let tree: KdTree = KdTree::build(points, BOUNDS);
+    check_tree(&tree, &brute, queries);
+    check_within(&tree, &brute, queries);
+    for cell_size in &[5.0, 25.0, 1000.0] {
+        let grid: Grid = Grid::build(points, BOUNDS, *cell_size);
+        check_index(&grid, &brute, queries);
+    }
+}
+
+#[test]
+fn random_points_match_brute_force() {
+    let uniform: Uniform<f32> = Uniform::new_inclusive(-150.0, 150.0);
+    for seed in 0..SEEDS {
+        let mut rng: StdRng = StdRng::seed_from_u64(seed);
+        let points: Vec<Point> = random_points(&mut rng, uniform, CAPACITY);
+        let mut queries: Vec<Point> = random_points(&mut rng, uniform, 16);
+        queries.extend_from_slice(&points[..16]);
+        check_points(&points, &queries);
+    }
+}
+
+#[test]
+fn duplicate_points_match_brute_force() {
+    let uniform: Uniform<f32> = Uniform::new_inclusive(-2.0, 2.0);
+    for seed in 0..SEEDS {
+        let mut rng: StdRng = StdRng::seed_from_u64(seed);
+        let points: Vec<Point> = random_points(&mut rng, uniform, CAPACITY)
+            .into_iter()
+            .map(|point| Point::new(point.x.round(), point.y.round()))
+            .collect();
+        check_points(&points, &points[..32]);
+    }
+}
+
+#[test]
+fn collinear_points_match_brute_force() {
+    let uniform: Uniform<f32> = Uniform::new_inclusive(-100.0, 100.0);
+    for seed in 0..SEEDS {
+        let mut rng: StdRng = StdRng::seed_from_u64(seed);
+        let values: Vec<f32> =
+            (0..CAPACITY).map(|_| rng.sample(uniform)).collect();
+        let vertical: Vec<Point> =
+            values.iter().map(|value| Point::new(3.0, *value)).collect();
+        let diagonal: Vec<Point> = values
+            .iter()
+            .map(|value| Point::new(*value, *value))
+            .collect();
+        let queries: Vec<Point> = random_points(&mut rng, uniform, 16);
+        check_points(&vertical, &queries);
+        check_points(&vertical, &vertical[..16]);
+        check_points(&diagonal, &queries);
+        check_points(&diagonal, &diagonal[..16]);
+    }
+}
+
+#[test]
+fn points_on_split_lines_match_brute_force() {
+    /* NOTE: Integer coordinates land every point on the split lines of its
+     * neighbours, and integer radii put candidates exactly on the boundary.
+     */
+    let uniform: Uniform<f32> = Uniform::new_inclusive(-10.0, 10.0);
+    for seed in 0..SEEDS {
+        let mut rng: StdRng = StdRng::seed_from_u64(seed);
+        let points: Vec<Point> = random_points(&mut rng, uniform, CAPACITY)
+            .into_iter()
+            .map(|point| Point::new(point.x.round(), point.y.round()))
+            .collect();
+        let queries: Vec<Point> = random_points(&mut rng, uniform, 32)
+            .into_iter()
+            .map(|point| Point::new(point.x.round(), point.y.round() + 0.5))
+            .collect();
+        check_points(&points, &queries);
+    }
+}
+
+#[test]
+fn empty_input_finds_nothing() {
+    let point: Point = Point::new(0.0, 0.0);
+    let tree: KdTree = KdTree::build(&[], BOUNDS);
+    let grid: Grid = Grid::build(&[], BOUNDS, 10.0);
+    assert!(tree.is_empty());
+    assert!(grid.is_empty());
+    assert!(search(&tree, &point, f32::INFINITY).is_empty());
+    assert!(search(&grid, &point, f32::INFINITY).is_empty());
+    assert_eq!(tree.nearest(&point), None);
+    assert_eq!(grid.nearest(&point), None);
+    assert!(tree.k_nearest(&point, 4).is_empty());
+    check_points(&[], &[point]);
+}
+
+#[test]
+fn nan_coordinates_are_never_found() {
+    let uniform: Uniform<f32> = Uniform::new_inclusive(-100.0, 100.0);
+    for seed in 0..SEEDS {
+        let mut rng: StdRng = StdRng::seed_from_u64(seed);
+        let mut points: Vec<Point> =
+            random_points(&mut rng, uniform, CAPACITY);
+        for i in 0..(CAPACITY / 4) {
+            let j: usize = rng.gen_range(0, CAPACITY);
+            points[j] = match i % 3 {
+                0 => Point::new(f32::NAN, points[j].y),
+                1 => Point::new(points[j].x, -f32::NAN),
+                _ => Point::new(f32::NAN, f32::NAN),
+            };
+        }
+        let queries: Vec<Point> = random_points(&mut rng, uniform, 16);
+        check_points(&points, &queries);
+    }
+}
+
+#[test]
+fn incremental_updates_match_brute_force() {
+    let uniform: Uniform<f32> = Uniform::new_inclusive(-100.0, 100.0);
+    let uniform_walk: Uniform<f32> = Uniform::new_inclusive(-10.0, 10.0);
+    let uniform_far: Uniform<f32> = Uniform::new_inclusive(-300.0, 300.0);
+    for seed in 0..SEEDS {
+        let mut rng: StdRng = StdRng::seed_from_u64(seed);
+        let mut points: Vec<Point> = random_points(&mut rng, uniform, 64);
+        let mut brute: BruteForce = BruteForce::build(&points);
+        let mut tree: KdTree = KdTree::build(&points, BOUNDS);
+        let mut grid: Grid = Grid::build(&points, BOUNDS, 10.0);
+        tree.set_rebalance_threshold(Some(0.5));
+        for _ in 0..8 {
+            for (i, point) in points.iter_mut().enumerate() {
+                let from: Point = *point;
+                point.x += rng.sample(uniform_walk);
+                point.y += rng.sample(uniform_walk);
+                assert!(brute.update(i, &from, *point));
+                assert!(tree.update(i, &from, *point));
+                assert!(grid.update(i, &from, *point));
+            }
+            let point: Point =
+                Point::new(rng.sample(uniform_far), rng.sample(uniform_far));
+            brute.insert(points.len(), point);
+            tree.insert(points.len(), point);
+            grid.insert(points.len(), point);
+            points.push(point);
+            let queries: Vec<Point> = random_points(&mut rng, uniform, 8);
+            check_tree(&tree, &brute, &queries);
+            check_within(&tree, &brute, &queries);
+            check_index(&grid, &brute, &queries);
+        }
+        assert_eq!(tree.len(), points.len());
+        assert_eq!(grid.len(), points.len());
+    }
+}
+
+#[test]
+fn three_dimensions_match_brute_force() {
+    let uniform: Uniform<f32> = Uniform::new_inclusive(-100.0, 100.0);
+    let bounds: Bounds<[f32; 3]> = Bounds {
+        lower: [-100.0; 3],
+        upper: [100.0; 3],
+    };
+    for seed in 0..SEEDS {
+        let mut rng: StdRng = StdRng::seed_from_u64(seed);
+        let points: Vec<[f32; 3]> = (0..CAPACITY)
+            .map(|_| {
+                [
+                    rng.sample(uniform),
+                    rng.sample(uniform),
+                    rng.sample(uniform),
+                ]
+            })
+            .collect();
+        let queries: Vec<[f32; 3]> = (0..16)
+            .map(|_| {
+                [
+                    rng.sample(uniform),
+                    rng.sample(uniform),
+                    rng.sample(uniform),
+                ]
+            })
+            .collect();
+        let brute: BruteForce<[f32; 3]> = BruteForce::build(&points);
+        let tree: KdTree<[f32; 3]> = KdTree::build(&points, bounds);
+        check_tree(&tree, &brute, &queries);
+    }
+}
+
+fn check_periodic(
+    index: &impl NeighborIndex,
+    points: &[Point],
+    queries: &[Point],
+) {
+    for query in queries {
+        for radius_squared in &RADII_SQUARED {
+            let mut neighbors: Vec<usize> = Vec::new();
+            index.search_periodic(
+                query,
+                *radius_squared,
+                &BOUNDS,
+                &mut neighbors,
+            );
+            let expected: Vec<usize> = (0..points.len())
+                .filter(|i| {
+                    (points[*i] != *query)
+                        && (periodic_squared_distance(
+                            query,
+                            &points[*i],
+                            &BOUNDS,
+                        ) < *radius_squared)
+                })
+                .collect();
+            assert_eq!(sorted(neighbors), expected);
+        }
+        let expected: Option<f32> = points
+            .iter()
+            .map(|point| periodic_squared_distance(query, point, &BOUNDS))
+            .fold(None, |nearest: Option<f32>, distance| {
+                Some(nearest.map_or(distance, |nearest| nearest.min(distance)))
+            });
+        let nearest: Option<f32> = index
+            .nearest_periodic(query, &BOUNDS)
+            .map(|neighbor| neighbor.squared_distance);
+        match (nearest, expected) {
+            (Some(nearest), Some(expected)) => {
+                assert!((nearest - expected).abs() <= (expected * 1.0e-5))
+            }
+            (nearest, expected) => assert_eq!(nearest, expected),
+        }
+    }
+}
+
+#[test]
+fn periodic_queries_use_minimum_image() {
+    let uniform: Uniform<f32> = Uniform::new(-100.0, 100.0);
+    for seed in 0..SEEDS {
+        let mut rng: StdRng = StdRng::seed_from_u64(seed);
+        let points: Vec<Point> = random_points(&mut rng, uniform, CAPACITY);
+        let mut queries: Vec<Point> = random_points(&mut rng, uniform, 16);
+        queries.push(Point::new(-100.0, -100.0));
+        queries.push(Point::new(99.5, 0.0));
+        let tree: KdTree = KdTree::build(&points, BOUNDS);
+        let grid: Grid = Grid::build(&points, BOUNDS, 25.0);
+        check_periodic(&tree, &points, &queries);
+        check_periodic(&grid, &points, &queries);
+    }
+}
+
+#[test]
+fn wrap_stays_inside_period() {
+    let uniform: Uniform<f32> = Uniform::new_inclusive(-1000.0, 1000.0);
+    let mut rng: StdRng = StdRng::seed_from_u64(0);
+    for point in random_points(&mut rng, uniform, CAPACITY) {
+        let wrapped: Point = BOUNDS.wrap(&point);
+        assert!(BOUNDS.contains(&wrapped));
+        let image: Point = BOUNDS.nearest_image(&wrapped, &point);
+        assert!((image - wrapped).length() < 1.0e-3);
+    }
+}
@@ -0,0 +1,82 @@
+use ranim_core::params;
+use ranim_core::params::Params as _;
+
+params! {
+    pub struct Params {
+        #[range(1.0..)]
+        edge: f64 = 800.0,
+        #[range(1..)]
+        capacity: usize = 512,
+        wrap: bool = false,
+        cyan: [f32; 4] = [0.5, 1.0, 0.87, 0.5],
+    }
+}
+
+#[test]
+fn defaults() {
+    let params: Params = Params::default();
+    assert_eq!(params.edge, 800.0);
+    assert_eq!(params.capacity, 512);
+    assert!(!params.wrap);
+}
+
+#[test]
+fn set() {
+    let mut params: Params = Params::default();
+    params.set("capacity", "64").unwrap();
+    params.set("wrap", "true").unwrap();
+    assert_eq!(
+        params,
+        Params {
+            capacity: 64,
+            wrap: true,
+            ..Params::default()
+        }
+    );
+}
+
+#[test]
+fn colors() {
+    let mut params: Params = Params::default();
+    params.set("cyan", "[0.1, 0.2, 0.3, 0.4]").unwrap();
+    assert_eq!(params.cyan, [0.1, 0.2, 0.3, 0.4]);
+    params.set("cyan", "1,1,1,1").unwrap();
+    assert_eq!(params.cyan, [1.0; 4]);
+    assert!(params.set("cyan", "1,1,1").is_err());
+    assert!(params.set("cyan", "1,1,1,1,1").is_err());
+}
+
+#[test]
+fn bad_values_and_names_are_rejected() {
+    let mut params: Params = Params::default();
+    assert!(params.set("capacity", "-1").is_err());
+    assert!(params.set("wrap", "yes").is_err());
+    assert!(params.set("unknown", "1").is_err());
+    assert_eq!(params, Params::default());
+}
+
+#[test]
+fn out_of_range_values_are_rejected() {
+    let mut params: Params = Params::default();
+    assert!(params.validate().is_ok());
+    for (name, value) in &[
+        ("capacity", "0"),
+        ("edge", "-1"),
+        ("edge", "NaN"),
+        ("edge", "inf"),
+        ("cyan", "NaN,1,1,1"),
+    ] {
+        params.set(name, value).unwrap();
+        assert!(params.validate().is_err(), "{} = {}", name, value);
+        params = Params::default();
+    }
+}
+
+#[test]
+fn describe() {
+    assert_eq!(
+        params::describe(&Params::default()),
+        "edge = 800\ncapacity = 512\nwrap = false\n\
+         cyan = [0.5, 1, 0.87, 0.5]\n"
+    );
+}
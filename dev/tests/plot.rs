@@ -0,0 +1,240 @@
+use rand::distributions::Uniform;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use ranim_core::geometry::{Bounds, Point};
+use ranim_core::plot;
+use ranim_core::plot::{Paper, Stroke};
+use std::collections::HashSet;
+
+const SEEDS: u64 = 8;
+const BOUNDS: Bounds = Bounds::new(-100.0, -100.0, 100.0, 100.0);
+
+fn key(a: &Point, b: &Point) -> [u32; 4] {
+    let mut key: [u32; 4] =
+        [a.x.to_bits(), a.y.to_bits(), b.x.to_bits(), b.y.to_bits()];
+    if [key[2], key[3]] < [key[0], key[1]] {
+        key = [key[2], key[3], key[0], key[1]];
+    }
+    key
+}
+
+fn stroke_segments(strokes: &[Stroke]) -> Vec<[u32; 4]> {
+    let mut segments: Vec<[u32; 4]> = strokes
+        .iter()
+        .flat_map(|stroke| {
+            stroke.windows(2).map(|pair| key(&pair[0], &pair[1]))
+        })
+        .collect();
+    segments.sort_unstable();
+    segments
+}
+
+fn segment_keys(segments: &[(Point, Point)]) -> Vec<[u32; 4]> {
+    let mut keys: Vec<[u32; 4]> =
+        segments.iter().map(|(a, b)| key(a, b)).collect();
+    keys.sort_unstable();
+    keys
+}
+
+#[test]
+fn clip_keeps_inner_segments_exact() {
+    let a: Point = Point::new(-10.5, 3.25);
+    let b: Point = Point::new(20.0, -7.0);
+    assert_eq!(BOUNDS.clip(&a, &b), Some((a, b)));
+}
+
+#[test]
+fn clip_cuts_at_the_border() {
+    let inside: Point = Point::new(50.0, 0.0);
+    let outside: Point = Point::new(150.0, 0.0);
+    assert_eq!(
+        BOUNDS.clip(&inside, &outside),
+        Some((inside, Point::new(100.0, 0.0))),
+    );
+    assert_eq!(
+        BOUNDS.clip(&Point::new(-150.0, 0.0), &outside),
+        Some((Point::new(-100.0, 0.0), Point::new(100.0, 0.0))),
+    );
+    assert_eq!(
+        BOUNDS.clip(&Point::new(150.0, 0.0), &Point::new(150.0, 50.0)),
+        None,
+    );
+    assert_eq!(
+        BOUNDS.clip(&Point::new(90.0, 150.0), &Point::new(150.0, 90.0)),
+        None,
+    );
+}
+
+#[test]
+fn merge_joins_a_shuffled_chain() {
+    let points: Vec<Point> = (0..6)
+        .map(|i| Point::new(i as f32, (i * i) as f32))
+        .collect();
+    let segments: Vec<(Point, Point)> = vec![
+        (points[3], points[2]),
+        (points[0], points[1]),
+        (points[4], points[5]),
+        (points[2], points[1]),
+        (points[3], points[4]),
+    ];
+    let strokes: Vec<Stroke> = plot::merge(&segments);
+    assert_eq!(strokes.len(), 1);
+    let mut stroke: Stroke = strokes[0].clone();
+    if stroke[0] != points[0] {
+        stroke.reverse();
+    }
+    assert_eq!(stroke, points);
+}
+
+#[test]
+fn merge_closes_rings() {
+    let points: Vec<Point> = vec![
+        Point::new(0.0, 0.0),
+        Point::new(1.0, 0.0),
+        Point::new(1.0, 1.0),
+        Point::new(0.0, 1.0),
+    ];
+    let segments: Vec<(Point, Point)> = (0..points.len())
+        .map(|i| (points[i], points[(i + 1) % points.len()]))
+        .collect();
+    let strokes: Vec<Stroke> = plot::merge(&segments);
+    assert_eq!(strokes.len(), 1);
+    assert_eq!(strokes[0].len(), 5);
+    assert_eq!(strokes[0].first(), strokes[0].last());
+}
+
+#[test]
+fn merge_covers_every_segment_once() {
+    for seed in 0..SEEDS {
+        let mut rng: StdRng = StdRng::seed_from_u64(seed);
+        let uniform: Uniform<usize> = Uniform::new(0, 24);
+        let points: Vec<Point> = (0..24)
+            .map(|_| Point::new(rng.gen_range(-50.0, 50.0), rng.gen()))
+            .collect();
+        let mut seen: HashSet<[u32; 4]> = HashSet::new();
+        let segments: Vec<(Point, Point)> = (0..64)
+            .map(|_| {
+                (points[rng.sample(uniform)], points[rng.sample(uniform)])
+            })
+            .filter(|(a, b)| (a != b) && seen.insert(key(a, b)))
+            .collect();
+        let strokes: Vec<Stroke> = plot::merge(&segments);
+        assert!(strokes.len() < segments.len());
+        assert_eq!(stroke_segments(&strokes), segment_keys(&segments));
+    }
+}
+
+#[test]
+fn order_reduces_travel() {
+    for seed in 0..SEEDS {
+        let mut rng: StdRng = StdRng::seed_from_u64(seed);
+        let uniform: Uniform<f32> = Uniform::new(0.0, 200.0);
+        let mut strokes: Vec<Stroke> = (0..64)
+            .map(|_| {
+                let a: Point =
+                    Point::new(rng.sample(uniform), rng.sample(uniform));
+                vec![a, a + Point::new(1.0, 1.0)]
+            })
+            .collect();
+        let start: Point = Point::new(0.0, 0.0);
+        let before: f32 = plot::travel(&strokes, start);
+        let segments: Vec<[u32; 4]> = stroke_segments(&strokes);
+        plot::order(&mut strokes, start);
+        assert!(plot::travel(&strokes, start) < before);
+        assert_eq!(stroke_segments(&strokes), segments);
+    }
+}
+
+#[test]
+fn order_reverses_strokes() {
+    let mut strokes: Vec<Stroke> = vec![
+        vec![Point::new(10.0, 0.0), Point::new(1.0, 0.0)],
+        vec![Point::new(20.0, 0.0), Point::new(11.0, 0.0)],
+    ];
+    plot::order(&mut strokes, Point::new(0.0, 0.0));
+    assert_eq!(
+        strokes,
+        vec![
+            vec![Point::new(1.0, 0.0), Point::new(10.0, 0.0)],
+            vec![Point::new(11.0, 0.0), Point::new(20.0, 0.0)],
+        ],
+    );
+    assert_eq!(plot::travel(&strokes, Point::new(0.0, 0.0)), 2.0);
+}
+
+#[test]
+fn fit_centers_inside_margins() {
+    let paper: Paper = Paper {
+        width: 200.0,
+        height: 300.0,
+        margin: 10.0,
+    };
+    let fit = paper.fit(&BOUNDS);
+    assert_eq!(fit(&BOUNDS.lower), Point::new(10.0, 240.0));
+    assert_eq!(fit(&BOUNDS.upper), Point::new(190.0, 60.0));
+    assert_eq!(fit(&Point::new(0.0, 0.0)), Point::new(100.0, 150.0));
+}
+
+#[test]
+fn paper_sizes_parse() {
+    assert_eq!(Paper::parse("A4", 15.0), Ok(Paper::A4));
+    assert_eq!(
+        Paper::parse("a3", 5.0),
+        Ok(Paper {
+            margin: 5.0,
+            ..Paper::A3
+        }),
+    );
+    assert_eq!(
+        Paper::parse("100x150.5", 0.0),
+        Ok(Paper {
+            width: 100.0,
+            height: 150.5,
+            margin: 0.0,
+        }),
+    );
+    assert!(Paper::parse("100", 0.0).is_err());
+    assert!(Paper::parse("0x100", 0.0).is_err());
+    assert!(Paper::parse("ax100", 0.0).is_err());
+}
+
+#[test]
+fn strokes_stay_on_the_paper() {
+    let paper: Paper = Paper::A4;
+    let segments: Vec<(Point, Point)> = vec![
+        (Point::new(-500.0, 0.0), Point::new(500.0, 0.0)),
+        (Point::new(0.0, -500.0), Point::new(0.0, 500.0)),
+        (Point::new(200.0, 200.0), Point::new(300.0, 300.0)),
+    ];
+    let strokes: Vec<Stroke> = plot::strokes(&segments, &BOUNDS, &paper);
+    assert_eq!(strokes.len(), 2);
+    for point in strokes.iter().flatten() {
+        assert!(paper.margin <= point.x);
+        assert!(point.x <= (paper.width - paper.margin));
+        assert!(paper.margin <= point.y);
+        assert!(point.y <= (paper.height - paper.margin));
+    }
+}
+
+#[test]
+fn hpgl_and_gcode_draw_each_stroke() {
+    let strokes: Vec<Stroke> = vec![
+        vec![Point::new(1.0, 2.0), Point::new(3.0, 4.0)],
+        vec![
+            Point::new(5.0, 5.0),
+            Point::new(6.0, 5.0),
+            Point::new(6.0, 6.0),
+        ],
+    ];
+    assert_eq!(
+        plot::hpgl(&strokes),
+        "IN;SP1;\nPU40,80;PD120,160;\nPU200,200;PD240,200,240,240;\n\
+         PU0,0;SP0;\n",
+    );
+    let gcode: String = plot::gcode(&strokes);
+    assert!(gcode.starts_with("G21\nG90\n"));
+    assert!(gcode.ends_with("M2\n"));
+    assert_eq!(gcode.matches("G0 X").count(), 3);
+    assert_eq!(gcode.matches("G1 X").count(), 3);
+    assert!(gcode.contains("G0 X5.000 Y5.000\n"));
+}
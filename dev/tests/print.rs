@@ -0,0 +1,78 @@
+use png::{Decoder, OutputInfo, Reader};
+use ranim_core::canvas::{Canvas, Style};
+use ranim_core::print::{self, Print};
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::path::PathBuf;
+
+const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+fn render(print: &Print) -> (OutputInfo, Vec<u8>) {
+    let path: PathBuf = env::temp_dir().join(format!(
+        "ranim-print-{}-{}.png",
+        std::process::id(),
+        print.size[0],
+    ));
+    print::write_png(&path, print, |canvas| {
+        canvas.clear(BLACK);
+        canvas.line(&Style::stroke(WHITE, 4.0), [-8.0, 0.0, 8.0, 0.0]);
+    })
+    .unwrap();
+    let (info, mut reader): (OutputInfo, Reader<File>) =
+        Decoder::new(File::open(&path).unwrap())
+            .read_info()
+            .unwrap();
+    let mut pixels: Vec<u8> = vec![0; info.buffer_size()];
+    reader.next_frame(&mut pixels).unwrap();
+    fs::remove_file(&path).unwrap();
+    (info, pixels)
+}
+
+fn white_rows(info: &OutputInfo, pixels: &[u8], x: u32) -> usize {
+    (0..info.height)
+        .filter(|y| pixels[(((y * info.width) + x) * 4) as usize] == 255)
+        .count()
+}
+
+#[test]
+fn strokes_scale_with_the_print() {
+    let small: Print = Print::new(32.0, 16.0, 4);
+    let large: Print = Print {
+        supersample: 2,
+        ..small.with_edge(128)
+    };
+    assert_eq!(large.size, [128, 64]);
+    assert!((large.scale() - 4.0).abs() < f64::EPSILON);
+    let (info, pixels): (OutputInfo, Vec<u8>) = render(&small);
+    assert_eq!(white_rows(&info, &pixels, 16), 4);
+    let (info, pixels): (OutputInfo, Vec<u8>) = render(&large);
+    assert_eq!((info.width, info.height), (128, 64));
+    assert_eq!(white_rows(&info, &pixels, 64), 16);
+}
+
+#[test]
+fn svg_size_is_physical_with_a_dpi() {
+    let print: Print = Print::new(32.0, 16.0, 4);
+    assert_eq!(print.svg_size(), ["32".to_string(), "16".to_string()]);
+    let print: Print = Print {
+        dpi: Some(254.0),
+        ..print.with_edge(1000)
+    };
+    assert_eq!(
+        print.svg_size(),
+        ["100.0mm".to_string(), "50.0mm".to_string()]
+    );
+}
+
+#[test]
+fn out_of_range_prints_are_rejected() {
+    let print: Print = Print::new(32.0, 16.0, 4);
+    assert!(print.check().is_ok());
+    assert!(print.with_edge(print::EDGE_MAX).check().is_ok());
+    assert!(print.with_edge(print::EDGE_MAX + 1).check().is_err());
+    assert!(Print::new(1e12, 16.0, 4).check().is_err());
+    assert!(Print::new(f64::NAN, 16.0, 4).check().is_err());
+    assert!(Print::new(0.4, 16.0, 4).check().is_err());
+}
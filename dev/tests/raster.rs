@@ -0,0 +1,138 @@
+use graphics::{Context, DrawState, Graphics};
+use ranim_core::raster::Raster;
+
+const WIDTH: u32 = 32;
+const HEIGHT: u32 = 16;
+
+const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+const HALF_WHITE: [f32; 4] = [1.0, 1.0, 1.0, 0.5];
+
+fn pixel(raster: &Raster, x: u32, y: u32) -> [u8; 4] {
+    let i: usize = (((y * raster.width()) + x) * 4) as usize;
+    let mut pixel: [u8; 4] = [0; 4];
+    pixel.copy_from_slice(&raster.pixels()[i..(i + 4)]);
+    pixel
+}
+
+fn cleared() -> Raster {
+    let mut raster: Raster = Raster::new(WIDTH, HEIGHT, 4);
+    raster.draw(|_, raster| graphics::clear(BLACK, raster));
+    raster
+}
+
+#[test]
+fn clear_fills_every_pixel() {
+    let raster: Raster = cleared();
+    assert!(raster
+        .pixels()
+        .chunks(4)
+        .all(|pixel| pixel == [0, 0, 0, 255]));
+}
+
+#[test]
+fn rectangle_covers_pixels_inside() {
+    let mut raster: Raster = cleared();
+    raster.draw(|context, raster| {
+        graphics::rectangle(
+            RED,
+            [4.0, 2.0, 8.0, 4.0],
+            context.transform,
+            raster,
+        )
+    });
+    assert_eq!(pixel(&raster, 4, 2), [255, 0, 0, 255]);
+    assert_eq!(pixel(&raster, 11, 5), [255, 0, 0, 255]);
+    assert_eq!(pixel(&raster, 3, 2), [0, 0, 0, 255]);
+    assert_eq!(pixel(&raster, 12, 5), [0, 0, 0, 255]);
+    assert_eq!(pixel(&raster, 4, 6), [0, 0, 0, 255]);
+}
+
+#[test]
+fn edges_are_anti_aliased() {
+    let mut raster: Raster = cleared();
+    raster.draw(|context, raster| {
+        graphics::rectangle(
+            WHITE,
+            [0.0, 0.0, 4.5, 4.0],
+            context.transform,
+            raster,
+        )
+    });
+    assert_eq!(pixel(&raster, 3, 1), [255, 255, 255, 255]);
+    assert_eq!(pixel(&raster, 4, 1), [128, 128, 128, 255]);
+    assert_eq!(pixel(&raster, 5, 1), [0, 0, 0, 255]);
+}
+
+#[test]
+fn shapes_blend_once_across_their_triangles() {
+    let mut raster: Raster = cleared();
+    raster.draw(|context, raster| {
+        graphics::ellipse(
+            HALF_WHITE,
+            [0.0, 0.0, 16.0, 16.0],
+            context.transform,
+            raster,
+        )
+    });
+    for x in 4..12 {
+        assert_eq!(pixel(&raster, x, 8), [128, 128, 128, 255]);
+    }
+}
+
+#[test]
+fn lines_draw_between_their_ends() {
+    let mut raster: Raster = cleared();
+    raster.draw(|context, raster| {
+        graphics::line(
+            WHITE,
+            1.0,
+            [2.0, 8.0, 30.0, 8.0],
+            context.transform,
+            raster,
+        )
+    });
+    for x in 4..28 {
+        assert_eq!(pixel(&raster, x, 8)[0], 255);
+        assert_eq!(pixel(&raster, x, 4), [0, 0, 0, 255]);
+    }
+}
+
+#[test]
+fn scissor_clips_drawing() {
+    let mut raster: Raster = cleared();
+    let context: Context = raster.context();
+    let draw_state: DrawState = DrawState::default().scissor([0, 0, 8, 8]);
+    graphics::Rectangle::new(RED).draw(
+        [0.0, 0.0, 32.0, 16.0],
+        &draw_state,
+        context.transform,
+        &mut raster,
+    );
+    assert_eq!(pixel(&raster, 7, 7), [255, 0, 0, 255]);
+    assert_eq!(pixel(&raster, 8, 7), [0, 0, 0, 255]);
+    assert_eq!(pixel(&raster, 7, 8), [0, 0, 0, 255]);
+}
+
+#[test]
+fn stencil_masks_drawing() {
+    let mut raster: Raster = cleared();
+    let context: Context = raster.context();
+    raster.clear_stencil(0);
+    graphics::Rectangle::new(WHITE).draw(
+        [0.0, 0.0, 8.0, 16.0],
+        &DrawState::new_clip(),
+        context.transform,
+        &mut raster,
+    );
+    assert_eq!(pixel(&raster, 2, 2), [0, 0, 0, 255]);
+    graphics::Rectangle::new(RED).draw(
+        [0.0, 0.0, 32.0, 16.0],
+        &DrawState::new_inside(),
+        context.transform,
+        &mut raster,
+    );
+    assert_eq!(pixel(&raster, 2, 2), [255, 0, 0, 255]);
+    assert_eq!(pixel(&raster, 20, 2), [0, 0, 0, 255]);
+}
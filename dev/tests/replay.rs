@@ -0,0 +1,83 @@
+use piston::input::{Button, Key};
+use ranim_core::params;
+use ranim_core::replay;
+use ranim_core::replay::{Input, Log, Session};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+params! {
+    pub struct Params {
+        #[range(1..)]
+        capacity: usize = 512,
+        wrap: bool = false,
+    }
+}
+
+const W: Input<Params> = Input::Press(Button::Keyboard(Key::W));
+
+fn record(path: PathBuf) -> Log<Params> {
+    let mut session: Session<Params> = Session::Record {
+        path: path.clone(),
+        log: Log::new(7, Params::default()),
+    };
+    assert_eq!(session.inputs(0, vec![W], false), vec![W]);
+    assert_eq!(session.inputs(0, vec![Input::Scroll(1.0)], true).len(), 1);
+    assert_eq!(session.inputs(1, Vec::new(), true), Vec::new());
+    let reload: Input<Params> = Input::Reload(Params {
+        wrap: true,
+        ..Params::default()
+    });
+    assert_eq!(session.inputs(3, vec![reload], false).len(), 1);
+    assert_eq!(session.finish(5).unwrap(), Some(path.as_path()));
+    replay::load(&path).unwrap()
+}
+
+#[test]
+fn record_then_replay() {
+    let path: PathBuf = env::temp_dir()
+        .join(format!("ranim-replay-{}.json", std::process::id()));
+    let log: Log<Params> = record(path.clone());
+    fs::remove_file(&path).unwrap();
+    assert_eq!((log.seed, log.steps), (7, Some(5)));
+    assert_eq!(
+        log.frames
+            .iter()
+            .map(|frame| frame.step)
+            .collect::<Vec<u64>>(),
+        vec![0, 3]
+    );
+    let mut session: Session<Params> = Session::Replay { log, cursor: 0 };
+    assert!(session.is_replay());
+    assert_eq!(session.inputs(0, vec![W], false), Vec::new());
+    assert_eq!(session.inputs(0, Vec::new(), true).len(), 2);
+    for step in 1..3 {
+        assert_eq!(session.inputs(step, vec![W], true), Vec::new());
+    }
+    match session.inputs(3, Vec::new(), true).as_slice() {
+        [Input::Reload(params)] => assert!(params.wrap),
+        _ => panic!(),
+    }
+    assert_eq!(session.finish(5).unwrap(), None);
+}
+
+#[test]
+fn invalid_logged_params_are_rejected() {
+    let path: PathBuf = env::temp_dir()
+        .join(format!("ranim-replay-bad-{}.json", std::process::id()));
+    let mut log: Log<Params> = Log::new(7, Params::default());
+    log.push(
+        3,
+        Input::Reload(Params {
+            capacity: 0,
+            ..Params::default()
+        }),
+    );
+    replay::save(&path, &log).unwrap();
+    assert!(replay::load::<Params>(&path).is_err());
+    log.frames.clear();
+    log.params.capacity = 0;
+    replay::save(&path, &log).unwrap();
+    assert!(replay::load::<Params>(&path).is_err());
+    fs::remove_file(&path).unwrap();
+}
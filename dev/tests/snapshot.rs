@@ -0,0 +1,152 @@
+#![allow(dead_code)]
+
+#[path = "../src/growth_lib/mod.rs"]
+mod growth_lib;
+
+use rand::distributions::Uniform;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+use ranim_core::geometry::Point;
+use ranim_core::kdtree_lib::KdTree;
+use ranim_core::params;
+use ranim_core::snapshot;
+use ranim_core::snapshot::Snapshot;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+params! {
+    pub struct Params {
+        edge: f64 = 800.0,
+        capacity: usize = 512,
+    }
+}
+
+#[test]
+fn restored_rng_continues_the_run() {
+    let mut rng: Pcg64 = Pcg64::seed_from_u64(42);
+    let points: Vec<Point> = (0..8)
+        .map(|_| Point::new(rng.gen(), rng.gen()))
+        .collect::<Vec<Point>>();
+    let snapshot: Snapshot<Params, Vec<Point>> = Snapshot {
+        seed: 42,
+        step: 8,
+        rng: rng.clone(),
+        params: Params {
+            capacity: 64,
+            ..Params::default()
+        },
+        state: points.clone(),
+    };
+    let path: PathBuf = env::temp_dir()
+        .join(format!("ranim-snapshot-{}.json", std::process::id()));
+    snapshot::save(&path, &snapshot).unwrap();
+    let mut restored: Snapshot<Params, Vec<Point>> =
+        snapshot::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(restored.seed, 42);
+    assert_eq!(restored.step, 8);
+    assert_eq!(restored.params, snapshot.params);
+    assert_eq!(restored.state, points);
+    for _ in 0..64 {
+        assert_eq!(restored.rng.gen::<u64>(), rng.gen::<u64>());
+    }
+}
+
+#[test]
+fn unknown_fields_are_rejected() {
+    let path: PathBuf = env::temp_dir()
+        .join(format!("ranim-snapshot-bad-{}.json", std::process::id()));
+    fs::write(&path, "{\"seed\": 1, \"frame\": 2}").unwrap();
+    assert!(snapshot::load::<Params, Vec<Point>>(&path).is_err());
+    fs::remove_file(&path).unwrap();
+}
+
+struct Growth {
+    rng: Pcg64,
+    nodes: Vec<growth_lib::Node>,
+    tree: KdTree,
+    uniform_init: Uniform<f32>,
+    uniform_walk: Uniform<f32>,
+}
+
+impl Growth {
+    fn new(rng: Pcg64, params: &growth_lib::Params) -> Self {
+        let point_rng_upper: f32 = params.point_rng_upper();
+        let mut tree: KdTree = KdTree::new();
+        tree.set_rebalance_threshold(Some(params.rebalance_threshold));
+        Self {
+            rng,
+            nodes: Vec::with_capacity(params.capacity),
+            tree,
+            uniform_init: Uniform::new_inclusive(
+                -point_rng_upper,
+                point_rng_upper,
+            ),
+            uniform_walk: Uniform::new_inclusive(
+                -params.walk_rng,
+                params.walk_rng,
+            ),
+        }
+    }
+
+    fn run(&mut self, steps: u64, params: &growth_lib::Params) {
+        for _ in 0..steps {
+            growth_lib::step(
+                &mut self.rng,
+                self.uniform_init,
+                self.uniform_walk,
+                &mut self.nodes,
+                &mut self.tree,
+                params,
+            );
+        }
+    }
+}
+
+/* NOTE: A restore rebuilds the neighbour index from scratch, so this only
+ * holds if growth does not depend on the shape of the index.
+ */
+#[test]
+fn restored_growth_matches_an_uninterrupted_run() {
+    let params: growth_lib::Params = growth_lib::Params::default();
+    let start = || {
+        let mut growth: Growth = Growth::new(Pcg64::seed_from_u64(1), &params);
+        growth_lib::init_nodes(
+            &mut growth.rng,
+            growth.uniform_init,
+            &mut growth.nodes,
+            &mut growth.tree,
+            &params,
+        );
+        growth
+    };
+    let mut uninterrupted: Growth = start();
+    uninterrupted.run(2000, &params);
+    let mut interrupted: Growth = start();
+    interrupted.run(777, &params);
+    let path: PathBuf = env::temp_dir()
+        .join(format!("ranim-snapshot-growth-{}.json", std::process::id()));
+    snapshot::save(
+        &path,
+        &Snapshot {
+            seed: 1,
+            step: 777,
+            rng: interrupted.rng,
+            params: params.clone(),
+            state: interrupted.nodes,
+        },
+    )
+    .unwrap();
+    let restored: Snapshot<growth_lib::Params, Vec<growth_lib::Node>> =
+        snapshot::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let mut growth: Growth = Growth::new(restored.rng, &restored.params);
+    growth.nodes = restored.state;
+    growth_lib::index_nodes(&growth.nodes, &mut growth.tree, &params);
+    growth.run(2000 - restored.step, &restored.params);
+    assert_eq!(
+        serde_json::to_string(&growth.nodes).unwrap(),
+        serde_json::to_string(&uninterrupted.nodes).unwrap(),
+    );
+}
@@ -0,0 +1,71 @@
+use ranim_core::canvas::{Canvas, Style};
+use ranim_core::svg::Svg;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const HALF_RED: [f32; 4] = [1.0, 0.0, 0.0, 0.5];
+
+#[test]
+fn view_box_is_centered() {
+    let svg: Svg = Svg::new(800.0, 600.0);
+    assert!(svg.to_document().starts_with(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"800\" \
+         height=\"600\" viewBox=\"-400 -300 800 600\">",
+    ));
+}
+
+#[test]
+fn clear_replaces_body() {
+    let mut svg: Svg = Svg::new(10.0, 10.0);
+    svg.circle(&Style::fill(WHITE), [0.0, 0.0], 1.0);
+    svg.clear(WHITE);
+    let document: String = svg.to_document();
+    assert!(!document.contains("<circle"));
+    assert!(document.contains(
+        "<rect x=\"-5\" y=\"-5\" width=\"10\" height=\"10\" \
+         fill=\"rgb(255,255,255)\"/>",
+    ));
+}
+
+#[test]
+fn shapes_keep_color_and_width() {
+    let mut svg: Svg = Svg::new(10.0, 10.0);
+    svg.line(&Style::stroke(HALF_RED, 1.15), [0.0, 0.0, 1.5, -2.25]);
+    svg.circle(&Style::fill(HALF_RED), [0.125, 0.0], 4.0);
+    svg.polyline(
+        &Style::stroke(WHITE, 0.8),
+        &[[0.0, 0.0], [1.0, 2.0], [3.0, 4.0]],
+    );
+    let document: String = svg.to_document();
+    assert!(document.contains(
+        "<line x1=\"0\" y1=\"0\" x2=\"1.5\" y2=\"-2.25\" \
+         stroke=\"rgb(255,0,0)\" stroke-opacity=\"0.5\" \
+         stroke-width=\"1.15\" stroke-linecap=\"square\"/>",
+    ));
+    assert!(document.contains(
+        "<circle cx=\"0.125\" cy=\"0\" r=\"4\" fill=\"rgb(255,0,0)\" \
+         fill-opacity=\"0.5\"/>",
+    ));
+    assert!(document.contains("points=\"0,0 1,2 3,4\" fill=\"none\""));
+    assert!(document.contains("stroke-width=\"0.8\""));
+}
+
+#[test]
+fn short_polylines_are_skipped() {
+    let mut svg: Svg = Svg::new(10.0, 10.0);
+    svg.polyline(&Style::stroke(WHITE, 1.0), &[[0.0, 0.0]]);
+    assert!(!svg.to_document().contains("<polyline"));
+}
+
+#[test]
+fn write_creates_the_directory() {
+    let dir: PathBuf =
+        env::temp_dir().join(format!("ranim-svg-{}", std::process::id()));
+    let path: PathBuf = dir.join("nested").join("out.svg");
+    let svg: Svg = Svg::new(10.0, 10.0);
+    svg.write(&path).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), svg.to_document());
+    fs::remove_dir_all(dir).unwrap();
+}